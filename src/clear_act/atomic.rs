@@ -0,0 +1,122 @@
+use std::path::{Path, PathBuf};
+use std::fs::{File, Metadata, OpenOptions};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use filetime::FileTime;
+
+use super::{ResultIO, open_read_no_atime};
+
+/// how a file is cleared
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClearMode {
+    /// the file is truncated (if needed) & rewritten in place
+    #[default]
+    InPlace,
+    /// the new content is written to a temp file in the same dir that gets 
+    /// all preserved metadata & then is renamed over the original file;
+    /// so a concurrent reader sees either the old or the new content
+    /// 
+    /// **\[!\]** the original inode is replaced, so hard links to it are broken
+    Atomic,
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let name = path.file_name().map(|x|x.to_string_lossy()).unwrap_or_default();
+    path.with_file_name(format!(".{name}.{}.{n}.cfpm-tmp", std::process::id()))
+}
+
+fn parent_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+/// creates the temp file accessible only by the owner 
+/// (until the permissions of the original are copied it can get the original content)
+fn create_temp(temp: &Path) -> std::io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.read(true).write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(temp)
+}
+
+/// copies owner, permissions & extended attributes of the original file to `f`
+#[cfg(unix)]
+fn copy_metadata(path: &Path, f: &File, md: &Metadata) -> ResultIO {
+    use std::os::unix::fs::MetadataExt;
+    use xattr::FileExt;
+
+    let temp_md = f.metadata()?;
+    if (temp_md.uid(), temp_md.gid()) != (md.uid(), md.gid()) {
+        std::os::unix::fs::fchown(f, Some(md.uid()), Some(md.gid()))?;
+    }
+    // after `chown` because it can reset setuid/setgid bits
+    f.set_permissions(md.permissions())?;
+
+    let names = match xattr::list(path) {
+        Ok(names) => names,
+        Err(err) if err.kind() == std::io::ErrorKind::Unsupported => return Ok(()),
+        Err(err) => return Err(err),
+    };
+    for name in names {
+        if let Some(value) = xattr::get(path, &name)? {
+            f.set_xattr(&name, &value)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn copy_metadata(_: &Path, f: &File, md: &Metadata) -> ResultIO {
+    f.set_permissions(md.permissions())
+}
+
+/// writes the new content by `action` to a temp file in the same dir, 
+/// copies metadata of the original (times, owner, permissions, xattrs), 
+/// flushes it to the disk & renames it over the original
+/// 
+/// if `truncate` is `false` then `action` gets the temp file with the original content
+/// 
+/// times of the parent dir (changed by the rename) are restored
+pub(crate) fn atomic_clear(
+    path: &Path, 
+    md: &Metadata, 
+    truncate: bool, 
+    action: impl FnOnce(&mut File) -> ResultIO,
+) -> ResultIO {
+    let parent = parent_dir(path);
+    let parent_md = std::fs::metadata(parent)?;
+    let parent_mtime = FileTime::from_last_modification_time(&parent_md);
+    let parent_atime = FileTime::from_last_access_time(&parent_md);
+
+    let temp = temp_path(path);
+    let mut f = create_temp(&temp)?;
+
+    let result = (|| {
+        if !truncate {
+            std::io::copy(&mut open_read_no_atime(path)?, &mut f)?;
+            std::io::Seek::seek(&mut f, std::io::SeekFrom::Start(0))?;
+        }
+        action(&mut f)?;
+
+        copy_metadata(path, &f, md)?;
+        let mtime = FileTime::from_last_modification_time(md);
+        let atime = FileTime::from_last_access_time(md);
+        filetime::set_file_handle_times(&f, Some(atime), Some(mtime))?;
+        f.sync_all()?;
+        drop(f);
+
+        std::fs::rename(&temp, path)
+    })();
+    if let Err(err) = result {
+        let _ = std::fs::remove_file(&temp);
+        return Err(err)
+    }
+
+    #[cfg(unix)]
+    File::open(parent)?.sync_all()?;
+    filetime::set_file_times(parent, parent_atime, parent_mtime)
+}
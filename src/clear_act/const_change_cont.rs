@@ -1,105 +1,145 @@
 use std::path::Path;
 use std::fs::{File, Metadata};
+use std::borrow::Cow;
 use std::io::Write;
 
-use super::{ResultIO, ClearFile, ClearDir};
+use super::{ResultIO, ClearFile, WrapD, ShredPass, TextStyle};
+use super::util::{read_head, rewrite};
 use crate::filter::{FileFilter, DirFilter};
 
 
 /// `ClearFile` that change content of a file to new const value
-pub struct ConstChangeContF<'filter, S: AsRef<str>> {
+/// 
+/// the new content is arbitrary bytes: `&str`, `String`, `&[u8]`, `Vec<u8>`, `Cow<[u8]>`, ...  
+/// so it can be a non UTF-8 content (e.g. a valid empty binary container)
+pub struct ConstChangeContF<'filter, S: AsRef<[u8]>> {
     new_content: S,
     file_filter: &'filter FileFilter,
+    shred_passes: Vec<ShredPass>,
+    match_style: bool,
 }
 
+/// how many bytes of the original content are used for text style detection
+const STYLE_SNIFF_LEN: u64 = 64 * 1024;
+
 impl ConstChangeContF<'static, &'static str> {
     pub fn new_clear_all() -> Self {
         Self {
             new_content: "", 
             file_filter: &FileFilter::EMPTY,
+            shred_passes: Vec::new(),
+            match_style: false,
         }
     }
 }
 
-impl<S: AsRef<str>> ConstChangeContF<'static, S> {
+impl<S: AsRef<[u8]>> ConstChangeContF<'static, S> {
     pub fn new_no_filter(new_content: S) -> Self {
         Self { 
             new_content, 
             file_filter: &FileFilter::EMPTY,
+            shred_passes: Vec::new(),
+            match_style: false,
         }
     }
 }
 
-impl<'filter, S: AsRef<str>> ConstChangeContF<'filter, S> {
+impl<'filter, S: AsRef<[u8]>> ConstChangeContF<'filter, S> {
     pub fn new(new_content: S, file_filter: &'filter FileFilter) -> Self {
         Self { 
             new_content, 
             file_filter,
+            shred_passes: Vec::new(),
+            match_style: false,
         }
     }
+
+    /// secure mode: before truncation the old content is overwritten in place
+    /// by each of `passes` (and flushed to the disk)
+    /// 
+    /// empty `passes` means no secure mode (the default); in secure mode the file can't be cleared 
+    /// atomically (`ClearMode::Atomic` gives `ErrorKind::InvalidInput` error)
+    pub fn set_shred_passes(&mut self, passes: Vec<ShredPass>) {
+        self.shred_passes = passes
+    }
+
+    /// if `true` the new content is normalized to the BOM & dominant line ending 
+    /// of the original file (see `TextStyle`)
+    pub fn set_match_style(&mut self, match_style: bool) {
+        self.match_style = match_style
+    }
 }
 
-impl<'filter, S: AsRef<str>> ClearFile for ConstChangeContF<'filter, S> {
+impl<'filter, S: AsRef<[u8]>> ClearFile for ConstChangeContF<'filter, S> {
     
     fn is_file_allow(&mut self, path: impl AsRef<Path>) -> bool {
         self.file_filter.is_allowed_unchecked(path).unwrap_or(false)
     }
 
-    fn clear_action(&mut self, f: &mut File, _: &Metadata) -> ResultIO {
+    fn clear_action(&mut self, f: &mut File, md: &Metadata) -> ResultIO {
+        let truncated = self.is_truncate();
+        let style = if self.match_style {
+            Some(TextStyle::detect(&read_head(f, STYLE_SNIFF_LEN)?))
+        } else {
+            None
+        };
+
+        if !self.shred_passes.is_empty() {
+            super::shred(f, md.len(), &self.shred_passes)?;
+        }
+
         let new_cont = self.new_content.as_ref();
-        if !new_cont.is_empty() {
-            write!(f, "{new_cont}")?;
+        let new_cont = match style {
+            Some(style) => style.apply(new_cont),
+            None => Cow::Borrowed(new_cont),
+        };
+        if !truncated {
+            rewrite(f, &new_cont)?;
+        } else if !new_cont.is_empty() {
+            f.write_all(&new_cont)?;
+        }
+
+        if !self.shred_passes.is_empty() {
+            f.sync_all()?;
         }
         Ok(())
     }
 
+    fn is_truncate(&mut self) -> bool {
+        self.shred_passes.is_empty() && !self.match_style
+    }
+
+    fn is_atomic_allowed(&mut self) -> bool {
+        self.shred_passes.is_empty()
+    }
+
 }
 
 
 /// `ClearDir` that change content of a file in a dir to new const value
 /// 
 /// by default is not recursive  
-pub struct ConstChangeContD<'filter, S: AsRef<str>> {
-    file_chg: ConstChangeContF<'filter, S>,
-    dir_filter: &'filter DirFilter,
-    recursive: bool,
-}
+pub type ConstChangeContD<'filter, S> = WrapD<'filter, ConstChangeContF<'filter, S>>;
 
 impl ConstChangeContD<'static, &'static str> {
     pub fn new_clear_all() -> Self {
-        Self {
-            file_chg: ConstChangeContF::new_clear_all(),
-            dir_filter: &DirFilter::EMPTY,
-            recursive: false,
-        }
+        WrapD::wrap_no_filter(ConstChangeContF::new_clear_all())
     }
 }
 
-impl<S: AsRef<str>> ConstChangeContD<'static, S> {
+impl<S: AsRef<[u8]>> ConstChangeContD<'static, S> {
     pub fn new_no_filter(new_content: S) -> Self {
-        Self {
-            file_chg: ConstChangeContF::new_no_filter(new_content),
-            dir_filter: &DirFilter::EMPTY,
-            recursive: false,
-        }
+        WrapD::wrap_no_filter(ConstChangeContF::new_no_filter(new_content))
     }
 }
 
-impl<'filter, S: AsRef<str>> ConstChangeContD<'filter, S> {
+impl<'filter, S: AsRef<[u8]>> ConstChangeContD<'filter, S> {
     pub fn new_no_file_filter(new_content: S, dir_filter: &'filter DirFilter) -> Self {
-        Self {
-            file_chg: ConstChangeContF::new_no_filter(new_content),
-            dir_filter,
-            recursive: false,
-        }
+        WrapD::wrap(ConstChangeContF::new_no_filter(new_content), dir_filter)
     }
 
     pub fn new_no_dir_filter(new_content: S, file_filter: &'filter FileFilter) -> Self {
-        Self {
-            file_chg: ConstChangeContF::new(new_content, file_filter),
-            dir_filter: &DirFilter::EMPTY,
-            recursive: false,
-        }
+        WrapD::wrap(ConstChangeContF::new(new_content, file_filter), &DirFilter::EMPTY)
     }
 
     pub fn new(
@@ -107,34 +147,6 @@ impl<'filter, S: AsRef<str>> ConstChangeContD<'filter, S> {
         file_filter: &'filter FileFilter, 
         dir_filter: &'filter DirFilter,
     ) -> Self {
-        Self {
-            file_chg: ConstChangeContF::new(new_content, file_filter),
-            dir_filter,
-            recursive: false,
-        }
-    }
-
-    pub fn set_recursive(&mut self, recursive: bool) {
-        self.recursive = recursive
+        WrapD::wrap(ConstChangeContF::new(new_content, file_filter), dir_filter)
     }
 }
-
-impl<'filter, S: AsRef<str>> ClearFile for ConstChangeContD<'filter, S> {
-    fn is_file_allow(&mut self, path: impl AsRef<Path>) -> bool {
-        self.file_chg.is_file_allow(path)
-    }
-
-    fn clear_action(&mut self, f: &mut File, md: &Metadata) -> ResultIO {
-        self.file_chg.clear_action(f, md)
-    }
-}
-
-impl<'filter, S: AsRef<str>> ClearDir for ConstChangeContD<'filter, S> {
-    fn is_dir_allow(&mut self, dir_path: impl AsRef<Path>) -> bool {
-        self.dir_filter.is_allowed_unchecked(dir_path)
-    }
-
-    fn is_recursive(&mut self, _: impl AsRef<Path>) -> bool {
-        self.recursive
-    }
-}
\ No newline at end of file
@@ -0,0 +1,162 @@
+use std::path::{Path, PathBuf};
+use std::fs::{File, Metadata};
+use std::io::Write;
+
+use super::{ResultIO, ClearFile};
+use super::rng::{Rng, hash64};
+use crate::filter::FileFilter;
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+const LOREM_WORDS: &[&str] = &[
+    "lorem", "ipsum", "dolor", "sit", "amet", "consectetur", "adipiscing", "elit",
+    "sed", "do", "eiusmod", "tempor", "incididunt", "ut", "labore", "et", "dolore",
+    "magna", "aliqua", "enim", "ad", "minim", "veniam", "quis", "nostrud",
+    "exercitation", "ullamco", "laboris", "nisi", "aliquip", "ex", "ea", "commodo",
+];
+
+/// kind of dummy content
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DummyKind {
+    /// pseudo-random bytes
+    Random,
+    /// lorem-ipsum ASCII text
+    Lorem,
+}
+
+/// `ClearFile` that replaces content of a file by dummy content of the same size
+/// 
+/// the content is deterministic: it depends only on `seed` and the path of the file
+/// relative to `root` if it is set, else to the cleared dir (the parent dir for a single file), 
+/// so reruns produce identical output however the path is spelled (`d/a.txt`, `./d/a.txt`, ...)
+pub struct DummyF<'filter> {
+    kind: DummyKind,
+    seed: u64,
+    /// canonicalized
+    root: Option<PathBuf>,
+    /// the dir that is being cleared (see `ClearFile::on_clear_root`), canonicalized
+    clear_root: Option<PathBuf>,
+    file_filter: &'filter FileFilter,
+}
+
+impl DummyF<'static> {
+    pub fn new_no_filter(kind: DummyKind, seed: u64) -> Self {
+        Self::new(kind, seed, &FileFilter::EMPTY)
+    }
+}
+
+impl<'filter> DummyF<'filter> {
+    pub fn new(kind: DummyKind, seed: u64, file_filter: &'filter FileFilter) -> Self {
+        Self {
+            kind,
+            seed,
+            root: None,
+            clear_root: None,
+            file_filter,
+        }
+    }
+
+    /// paths of files are taken relative to the `root` for the content seeding 
+    /// (instead of the cleared dir)
+    pub fn set_root(&mut self, root: Option<PathBuf>) -> ResultIO {
+        self.root = root.map(std::fs::canonicalize).transpose()?;
+        Ok(())
+    }
+
+    fn path_rng(&self, path: Option<&Path>) -> Rng {
+        let mut bytes = self.seed.to_le_bytes().to_vec();
+        if let Some(path) = path {
+            let path = canonical_parent(path);
+            let root = self.root.as_deref()
+                .or(self.clear_root.as_deref())
+                .or(path.parent());
+            let path = root.and_then(|root|path.strip_prefix(root).ok()).unwrap_or(&path);
+            bytes.extend_from_slice(path_slash::PathExt::to_slash_lossy(path).as_bytes());
+        }
+        Rng::new(hash64(&bytes))
+    }
+
+    fn dummy_action(&mut self, path: Option<&Path>, f: &mut File, len: u64) -> ResultIO {
+        let mut rng = self.path_rng(path);
+        let mut lorem = Lorem::default();
+
+        let mut chunk = vec![0; CHUNK_SIZE.min(usize::try_from(len).unwrap_or(usize::MAX))];
+        let mut left = len;
+        while left > 0 {
+            let n = usize::try_from(left).unwrap_or(usize::MAX).min(chunk.len());
+            match self.kind {
+                DummyKind::Random => rng.fill(&mut chunk[..n]),
+                DummyKind::Lorem => lorem.fill(&mut rng, &mut chunk[..n]),
+            }
+            f.write_all(&chunk[..n])?;
+            left -= n as u64;
+        }
+        Ok(())
+    }
+}
+
+/// the `path` with canonicalized parent dir (a symlink keeps its name)
+fn canonical_parent(path: &Path) -> PathBuf {
+    let parent = match path.parent() {
+        Some(parent) if parent.as_os_str().is_empty() => Path::new("."),
+        Some(parent) => parent,
+        None => return path.to_path_buf(),
+    };
+    match (std::fs::canonicalize(parent), path.file_name()) {
+        (Ok(parent), Some(name)) => parent.join(name),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// lorem-ipsum generator that can continue a text between chunks
+#[derive(Default)]
+struct Lorem {
+    /// rest of the current word (with separator)
+    pending: Vec<u8>,
+    words_in_line: usize,
+}
+
+impl Lorem {
+    fn fill(&mut self, rng: &mut Rng, buf: &mut [u8]) {
+        let mut i = 0;
+        while i < buf.len() {
+            if self.pending.is_empty() {
+                let word = LOREM_WORDS[(rng.next_u64() % LOREM_WORDS.len() as u64) as usize];
+                self.pending.extend(word.bytes().rev());
+                self.words_in_line += 1;
+                let sep = if self.words_in_line >= 8 + (rng.next_u64() % 8) as usize {
+                    self.words_in_line = 0;
+                    b'\n'
+                } else {
+                    b' '
+                };
+                self.pending.insert(0, sep);
+            }
+            // `pending` is stored reversed
+            buf[i] = self.pending.pop().unwrap_or(b' ');
+            i += 1;
+        }
+    }
+}
+
+impl<'filter> ClearFile for DummyF<'filter> {
+    fn is_file_allow(&mut self, path: impl AsRef<Path>) -> bool {
+        self.file_filter.is_allowed_unchecked(path).unwrap_or(false)
+    }
+
+    fn clear_action(&mut self, f: &mut File, md: &Metadata) -> ResultIO {
+        self.dummy_action(None, f, md.len())
+    }
+
+    fn clear_path_action(&mut self, path: &Path, f: &mut File, md: &Metadata) -> ResultIO {
+        self.dummy_action(Some(path), f, md.len())
+    }
+
+    fn on_clear_root(&mut self, root: Option<&Path>) {
+        self.clear_root = root.and_then(|root|std::fs::canonicalize(root).ok())
+    }
+
+    fn is_truncate(&mut self) -> bool {
+        false
+    }
+}
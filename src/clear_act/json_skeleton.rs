@@ -0,0 +1,70 @@
+use std::path::Path;
+use std::fs::{File, Metadata};
+
+use serde_json::Value;
+
+use super::{ResultIO, ClearFile};
+use super::util::{read_all, rewrite};
+use crate::filter::FileFilter;
+
+/// keeps the structure of the JSON value but replaces all leaf values
+/// by type-appropriate zero values (`""`, `0`, `false`, `null`)
+/// 
+/// arrays are shrunk to the skeleton of their first element
+pub fn json_skeleton(value: Value) -> Value {
+    match value {
+        Value::Null => Value::Null,
+        Value::Bool(_) => Value::Bool(false),
+        Value::Number(num) => if num.is_f64() { 
+            Value::from(0.0) 
+        } else { 
+            Value::from(0) 
+        },
+        Value::String(_) => Value::String(String::new()),
+        Value::Array(arr) => {
+            Value::Array(arr.into_iter().take(1).map(json_skeleton).collect())
+        }
+        Value::Object(obj) => {
+            Value::Object(obj.into_iter().map(|(k, v)|(k, json_skeleton(v))).collect())
+        }
+    }
+}
+
+/// `ClearFile` that change content of a JSON file to its skeleton (see `fn json_skeleton`)
+/// 
+/// if the file is not a valid JSON then `ErrorKind::InvalidData` error is returned
+/// and the file content stays unchanged (in dir clearing such files are skipped, see `ClearReport::invalid`)
+pub struct JsonSkeletonF<'filter> {
+    file_filter: &'filter FileFilter,
+}
+
+impl JsonSkeletonF<'static> {
+    pub fn new_no_filter() -> Self {
+        Self::new(&FileFilter::EMPTY)
+    }
+}
+
+impl<'filter> JsonSkeletonF<'filter> {
+    pub fn new(file_filter: &'filter FileFilter) -> Self {
+        Self { file_filter }
+    }
+}
+
+impl<'filter> ClearFile for JsonSkeletonF<'filter> {
+    fn is_file_allow(&mut self, path: impl AsRef<Path>) -> bool {
+        self.file_filter.is_allowed_unchecked(path).unwrap_or(false)
+    }
+
+    fn clear_action(&mut self, f: &mut File, _: &Metadata) -> ResultIO {
+        // `From<serde_json::Error>` gives `UnexpectedEof` for empty & truncated files
+        let value: Value = serde_json::from_slice(&read_all(f)?)
+            .map_err(|err|std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+        let mut skeleton = serde_json::to_vec(&json_skeleton(value))?;
+        skeleton.push(b'\n');
+        rewrite(f, &skeleton)
+    }
+
+    fn is_truncate(&mut self) -> bool {
+        false
+    }
+}
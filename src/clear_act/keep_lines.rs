@@ -0,0 +1,101 @@
+use std::path::Path;
+use std::fs::{File, Metadata};
+
+use super::{ResultIO, ClearFile};
+use super::util::{read_all, rewrite};
+use crate::filter::FileFilter;
+
+/// what to do with the content of each line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineMask {
+    /// the line becomes empty
+    Blank,
+    /// each non-whitespace char of the line is replaced by the byte (whitespaces are kept)
+    Mask(u8),
+}
+
+/// keeps the same number of lines but blanks or masks each line content
+/// 
+/// if `keep_endings` is `true` then each line keeps its ending (CRLF or LF), 
+/// otherwise all endings become LF
+pub fn keep_lines(text: &[u8], mask: LineMask, keep_endings: bool) -> Vec<u8> {
+    let mut out = Vec::with_capacity(match mask {
+        LineMask::Blank => text.iter().filter(|&&b|b == b'\n').count() * 2,
+        LineMask::Mask(_) => text.len(),
+    });
+
+    let mut lines = text.split(|&b|b == b'\n').peekable();
+    while let Some(line) = lines.next() {
+        let is_last = lines.peek().is_none();
+        let (line, crlf) = match line.strip_suffix(b"\r") {
+            Some(line) if !is_last => (line, true),
+            _ => (line, false),
+        };
+
+        if let LineMask::Mask(mask) = mask {
+            for &b in line {
+                if b.is_ascii_whitespace() {
+                    out.push(b);
+                } else if b & 0xC0 != 0x80 {
+                    // one mask byte per UTF-8 char
+                    out.push(mask);
+                }
+            }
+        }
+
+        if !is_last {
+            if crlf && keep_endings { out.push(b'\r') }
+            out.push(b'\n');
+        }
+    }
+    out
+}
+
+/// `ClearFile` that keeps the number of lines of a text file but blanks or masks their content
+/// (see `fn keep_lines`)
+/// 
+/// by default lines are blanked & line endings are kept
+pub struct KeepLinesF<'filter> {
+    mask: LineMask,
+    keep_endings: bool,
+    file_filter: &'filter FileFilter,
+}
+
+impl KeepLinesF<'static> {
+    pub fn new_no_filter() -> Self {
+        Self::new(&FileFilter::EMPTY)
+    }
+}
+
+impl<'filter> KeepLinesF<'filter> {
+    pub fn new(file_filter: &'filter FileFilter) -> Self {
+        Self {
+            mask: LineMask::Blank,
+            keep_endings: true,
+            file_filter,
+        }
+    }
+
+    pub fn set_mask(&mut self, mask: LineMask) {
+        self.mask = mask
+    }
+
+    pub fn set_keep_endings(&mut self, keep_endings: bool) {
+        self.keep_endings = keep_endings
+    }
+}
+
+impl<'filter> ClearFile for KeepLinesF<'filter> {
+    fn is_file_allow(&mut self, path: impl AsRef<Path>) -> bool {
+        self.file_filter.is_allowed_unchecked(path).unwrap_or(false)
+    }
+
+    fn clear_action(&mut self, f: &mut File, _: &Metadata) -> ResultIO {
+        let text = read_all(f)?;
+        rewrite(f, &keep_lines(&text, self.mask, self.keep_endings))
+    }
+
+    fn is_truncate(&mut self) -> bool {
+        false
+    }
+}
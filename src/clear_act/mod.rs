@@ -1,11 +1,43 @@
-
 type ResultIO = std::io::Result<()>;
 
 mod traits;
 pub use traits::{ClearFile, ClearDir};
 
+mod report;
+pub use report::{ClearReport, ClearProgress, HardLinkGroup, HardLinkPolicy};
+
+mod atomic;
+pub use atomic::ClearMode;
+
+mod no_atime;
+pub use no_atime::{open_no_atime, open_read_no_atime};
+
+mod rng;
+mod util;
+mod text_style;
+pub use text_style::TextStyle;
+mod shred;
+pub use shred::{ShredPass, shred};
+
+mod wrap_dir;
+pub use wrap_dir::{WrapD, FileConfirm, ProgressFn};
+
 // [+] impls
 mod const_change_cont;
 pub use const_change_cont::{ConstChangeContF, ConstChangeContD};
+mod same_size_fill;
+pub use same_size_fill::SameSizeFillF;
+mod punch_hole;
+pub use punch_hole::{PunchHoleF, PunchFallback};
+mod stub;
+pub use stub::{StubF, StubFormat};
+mod json_skeleton;
+pub use json_skeleton::{JsonSkeletonF, json_skeleton};
+mod src_stub;
+pub use src_stub::{SrcStubF, SrcLang, src_stub};
+mod dummy;
+pub use dummy::{DummyF, DummyKind};
+mod keep_lines;
+pub use keep_lines::{KeepLinesF, LineMask, keep_lines};
 // [-] impls
 
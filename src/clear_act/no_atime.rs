@@ -0,0 +1,29 @@
+use std::path::Path;
+use std::fs::{File, OpenOptions};
+
+/// opens the file by `options` so reads from it don't update its atime (`O_NOATIME`)
+/// 
+/// `O_NOATIME` is permitted only for the owner of the file (or a privileged process),
+/// if it is not permitted (or not supported by the OS) the file is opened by `options` as is
+pub fn open_no_atime(path: impl AsRef<Path>, options: &OpenOptions) -> std::io::Result<File> {
+    let path = path.as_ref();
+
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        let mut no_atime = options.clone();
+        no_atime.custom_flags(libc::O_NOATIME);
+        match no_atime.open(path) {
+            Err(err) if err.raw_os_error() == Some(libc::EPERM) => {}
+            result => return result,
+        }
+    }
+
+    options.open(path)
+}
+
+/// opens the file for reading without atime update (see `fn open_no_atime`)
+pub fn open_read_no_atime(path: impl AsRef<Path>) -> std::io::Result<File> {
+    open_no_atime(path, OpenOptions::new().read(true))
+}
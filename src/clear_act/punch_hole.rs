@@ -0,0 +1,113 @@
+use std::path::Path;
+use std::fs::{File, Metadata};
+
+use super::{ResultIO, ClearFile};
+use super::same_size_fill::fill_repeated;
+use crate::filter::FileFilter;
+
+/// what to do if the filesystem (or OS) does not support punching holes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PunchFallback {
+    /// overwrite the content by zeros (storage is not freed)
+    ZeroFill,
+    /// return `ErrorKind::Unsupported` error (the file stays unchanged)
+    Error,
+}
+
+/// `ClearFile` that deallocates the storage of a file (makes it sparse)
+/// while keeping its logical size, so the content reads as zeros
+/// 
+/// by default the fallback is `PunchFallback::ZeroFill`
+pub struct PunchHoleF<'filter> {
+    file_filter: &'filter FileFilter,
+    fallback: PunchFallback,
+    freed_bytes: u64,
+}
+
+impl PunchHoleF<'static> {
+    pub fn new_no_filter() -> Self {
+        Self::new(&FileFilter::EMPTY)
+    }
+}
+
+impl<'filter> PunchHoleF<'filter> {
+    pub fn new(file_filter: &'filter FileFilter) -> Self {
+        Self {
+            file_filter,
+            fallback: PunchFallback::ZeroFill,
+            freed_bytes: 0,
+        }
+    }
+
+    pub fn set_fallback(&mut self, fallback: PunchFallback) {
+        self.fallback = fallback
+    }
+
+    /// total bytes of the storage that were actually freed by all the cleared files
+    pub fn freed_bytes(&self) -> u64 {
+        self.freed_bytes
+    }
+}
+
+/// # return
+/// * `Ok(true)` if the hole was punched
+/// * `Ok(false)` if punching holes is not supported
+#[cfg(target_os = "linux")]
+fn punch_hole(f: &File, len: u64) -> std::io::Result<bool> {
+    use std::os::unix::io::AsRawFd;
+
+    if len == 0 { return Ok(true) }
+    let len = libc::off_t::try_from(len).unwrap_or(libc::off_t::MAX);
+    let mode = libc::FALLOC_FL_PUNCH_HOLE | libc::FALLOC_FL_KEEP_SIZE;
+    // SAFETY: the fd is valid while `f` is alive
+    let ret = unsafe { libc::fallocate(f.as_raw_fd(), mode, 0, len) };
+    if ret == 0 { return Ok(true) }
+
+    let err = std::io::Error::last_os_error();
+    match err.raw_os_error() {
+        Some(libc::EOPNOTSUPP | libc::ENOSYS) => Ok(false),
+        _ => Err(err),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn punch_hole(_: &File, _: u64) -> std::io::Result<bool> {
+    Ok(false)
+}
+
+/// allocated storage in bytes
+#[cfg(unix)]
+fn allocated_bytes(md: &Metadata) -> u64 {
+    std::os::unix::fs::MetadataExt::blocks(md) * 512
+}
+
+#[cfg(not(unix))]
+fn allocated_bytes(_: &Metadata) -> u64 {
+    0
+}
+
+impl<'filter> ClearFile for PunchHoleF<'filter> {
+    fn is_file_allow(&mut self, path: impl AsRef<Path>) -> bool {
+        self.file_filter.is_allowed_unchecked(path).unwrap_or(false)
+    }
+
+    fn clear_action(&mut self, f: &mut File, md: &Metadata) -> ResultIO {
+        if !punch_hole(f, md.len())? {
+            match self.fallback {
+                PunchFallback::ZeroFill => fill_repeated(f, &[0], md.len())?,
+                PunchFallback::Error => return Err(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported, 
+                    "punching holes is not supported by the filesystem",
+                )),
+            }
+        }
+
+        let allocated_after = allocated_bytes(&f.metadata()?);
+        self.freed_bytes += allocated_bytes(md).saturating_sub(allocated_after);
+        Ok(())
+    }
+
+    fn is_truncate(&mut self) -> bool {
+        false
+    }
+}
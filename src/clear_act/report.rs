@@ -0,0 +1,61 @@
+use std::path::PathBuf;
+
+/// what to do with files that have multiple hard links
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HardLinkPolicy {
+    /// the file (inode) is cleared once (in place, so under all its links) 
+    /// & all its link paths met in the traversal are reported
+    /// 
+    /// the file is cleared in place even if the clear mode is `ClearMode::Atomic`
+    /// (an atomic clearing would change only one of the links)
+    #[default]
+    ClearOnce,
+    /// multi-linked files are not cleared (but reported)
+    Skip,
+    /// copy-on-clear: each matched path is cleared atomically (`ClearMode::Atomic`)
+    /// so it gets a new inode & other links keep the old content
+    BreakLink,
+}
+
+/// a file with multiple hard links
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HardLinkGroup {
+    /// paths of the file that were met in the traversal
+    pub paths: Vec<PathBuf>,
+    /// number of links to the file (some of them can be outside of the traversed dir)
+    pub nlink: u64,
+    /// was the file cleared (by any of `paths`)?
+    pub cleared: bool,
+}
+
+/// report of a dir clearing
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClearReport {
+    /// paths of cleared files
+    pub cleared: Vec<PathBuf>,
+    /// files with multiple hard links that were met in the traversal
+    pub hard_links: Vec<HardLinkGroup>,
+    /// files that were skipped because their content doesn't fit the action 
+    /// (it failed with `ErrorKind::InvalidData`, e.g. a non JSON file for the JSON skeleton)
+    pub invalid: Vec<PathBuf>,
+}
+
+impl ClearReport {
+    pub fn append(&mut self, mut other: Self) {
+        self.cleared.append(&mut other.cleared);
+        self.hard_links.append(&mut other.hard_links);
+        self.invalid.append(&mut other.invalid);
+    }
+}
+
+/// progress of a dir clearing (see `ClearDir::on_progress`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClearProgress {
+    /// files met in the traversal (including not allowed ones)
+    pub visited: u64,
+    /// cleared files
+    pub cleared: u64,
+    /// decrease of the storage used by cleared files (allocated blocks on unix, size elsewhere),
+    /// counted only if `ClearDir::has_progress`
+    pub freed_bytes: u64,
+}
@@ -0,0 +1,41 @@
+/// small non-cryptographic PRNG (SplitMix64)
+/// 
+/// it is used for overwriting content, not for anything security-sensitive by itself
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// seed from current time & process id
+    pub fn new_unseeded() -> Self {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d|d.as_nanos() as u64)
+            .unwrap_or(0);
+        Self::new(nanos ^ (u64::from(std::process::id()) << 32))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    pub fn fill(&mut self, buf: &mut [u8]) {
+        for chunk in buf.chunks_mut(8) {
+            let rand = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&rand[..chunk.len()]);
+        }
+    }
+}
+
+/// FNV-1a 64 hash (stable between runs & platforms unlike `DefaultHasher`)
+pub(crate) fn hash64(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xCBF2_9CE4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0000_0100_0000_01B3)
+    })
+}
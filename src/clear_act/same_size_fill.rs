@@ -0,0 +1,85 @@
+use std::path::Path;
+use std::fs::{File, Metadata};
+use std::io::Write;
+
+use super::{ResultIO, ClearFile};
+use crate::filter::FileFilter;
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// `ClearFile` that overwrites a file in place by a repeated byte pattern
+/// while keeping the exact original length
+/// 
+/// an empty pattern is the same as zero pattern (`[0]`)
+pub struct SameSizeFillF<'filter, P: AsRef<[u8]>> {
+    pattern: P,
+    file_filter: &'filter FileFilter,
+}
+
+impl SameSizeFillF<'static, &'static [u8]> {
+    pub fn new_zero_no_filter() -> Self {
+        Self {
+            pattern: &[0],
+            file_filter: &FileFilter::EMPTY,
+        }
+    }
+}
+
+impl<'filter> SameSizeFillF<'filter, &'static [u8]> {
+    pub fn new_zero(file_filter: &'filter FileFilter) -> Self {
+        Self {
+            pattern: &[0],
+            file_filter,
+        }
+    }
+}
+
+impl<P: AsRef<[u8]>> SameSizeFillF<'static, P> {
+    pub fn new_no_filter(pattern: P) -> Self {
+        Self {
+            pattern,
+            file_filter: &FileFilter::EMPTY,
+        }
+    }
+}
+
+impl<'filter, P: AsRef<[u8]>> SameSizeFillF<'filter, P> {
+    pub fn new(pattern: P, file_filter: &'filter FileFilter) -> Self {
+        Self {
+            pattern,
+            file_filter,
+        }
+    }
+}
+
+/// writes `len` bytes of repeated `pattern` into `f` from its current position
+pub(crate) fn fill_repeated(f: &mut File, pattern: &[u8], len: u64) -> ResultIO {
+    let pattern = if pattern.is_empty() { &[0] } else { pattern };
+
+    // chunk is a multiple of the pattern so the pattern is continuous between chunks
+    let chunk_len = pattern.len() * (CHUNK_SIZE / pattern.len()).max(1);
+    let chunk_len = chunk_len.min(usize::try_from(len).unwrap_or(usize::MAX));
+    let chunk: Vec<u8> = pattern.iter().copied().cycle().take(chunk_len).collect();
+
+    let mut left = len;
+    while left > 0 {
+        let n = usize::try_from(left).unwrap_or(usize::MAX).min(chunk.len());
+        f.write_all(&chunk[..n])?;
+        left -= n as u64;
+    }
+    Ok(())
+}
+
+impl<'filter, P: AsRef<[u8]>> ClearFile for SameSizeFillF<'filter, P> {
+    fn is_file_allow(&mut self, path: impl AsRef<Path>) -> bool {
+        self.file_filter.is_allowed_unchecked(path).unwrap_or(false)
+    }
+
+    fn clear_action(&mut self, f: &mut File, md: &Metadata) -> ResultIO {
+        fill_repeated(f, self.pattern.as_ref(), md.len())
+    }
+
+    fn is_truncate(&mut self) -> bool {
+        false
+    }
+}
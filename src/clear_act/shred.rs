@@ -0,0 +1,46 @@
+use std::fs::File;
+use std::io::{Seek, SeekFrom, Write};
+
+use super::ResultIO;
+use super::rng::Rng;
+use super::same_size_fill::fill_repeated;
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// one overwrite pass of secure clearing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShredPass {
+    Zero,
+    /// pseudo-random bytes
+    Random,
+    Byte(u8),
+}
+
+/// overwrites first `len` bytes of the file in place by each pass in turn
+/// 
+/// each pass is flushed to the disk (`fsync`) before the next one  
+pub fn shred(f: &mut File, len: u64, passes: &[ShredPass]) -> ResultIO {
+    let mut rng = Rng::new_unseeded();
+
+    for pass in passes {
+        f.seek(SeekFrom::Start(0))?;
+        match pass {
+            ShredPass::Zero => fill_repeated(f, &[0], len)?,
+            ShredPass::Byte(byte) => fill_repeated(f, &[*byte], len)?,
+            ShredPass::Random => {
+                let mut chunk = vec![0; CHUNK_SIZE.min(usize::try_from(len).unwrap_or(usize::MAX))];
+                let mut left = len;
+                while left > 0 {
+                    let n = usize::try_from(left).unwrap_or(usize::MAX).min(chunk.len());
+                    rng.fill(&mut chunk[..n]);
+                    f.write_all(&chunk[..n])?;
+                    left -= n as u64;
+                }
+            }
+        }
+        f.sync_all()?;
+    }
+
+    f.seek(SeekFrom::Start(0))?;
+    Ok(())
+}
@@ -0,0 +1,306 @@
+use std::path::Path;
+use std::fs::{File, Metadata};
+
+use super::{ResultIO, ClearFile};
+use super::util::{read_all, rewrite};
+use crate::filter::FileFilter;
+
+/// language of a source file that can be stubbed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SrcLang {
+    Rust,
+    /// C & C++
+    C,
+}
+
+impl SrcLang {
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let ext = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        Some(match ext.as_str() {
+            "rs" => Self::Rust,
+            "c" | "h" | "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" => Self::C,
+            _ => return None,
+        })
+    }
+
+    fn placeholder(self) -> &'static str {
+        match self {
+            Self::Rust => "{ unimplemented!() }",
+            Self::C => "{}",
+        }
+    }
+}
+
+/// C++ words that can be between `)` of params and `{` of a function body
+const C_FN_QUALIFIERS: &[&str] = &["const", "noexcept", "override", "final", "volatile"];
+
+fn is_ident_start(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'_' || b >= 0x80
+}
+
+fn is_ident_char(b: u8) -> bool {
+    is_ident_start(b) || b.is_ascii_digit()
+}
+
+/// if at `i` starts a comment, a string or a char literal (or a preprocessor line for C)
+/// then returns the index right after it
+fn skip_non_code(src: &str, i: usize, lang: SrcLang) -> Option<usize> {
+    let b = src.as_bytes();
+    let next = b.get(i + 1).copied();
+    match b[i] {
+        b'/' if next == Some(b'/') => {
+            Some(b[i..].iter().position(|&c|c == b'\n').map(|p|i + p).unwrap_or(b.len()))
+        }
+        b'/' if next == Some(b'*') => {
+            // Rust block comments can be nested
+            let mut depth = 0;
+            let mut j = i;
+            while j < b.len() {
+                if b[j..].starts_with(b"/*") {
+                    depth += 1;
+                    j += 2;
+                } else if b[j..].starts_with(b"*/") {
+                    depth -= 1;
+                    j += 2;
+                    if depth == 0 || lang == SrcLang::C { return Some(j) }
+                } else {
+                    j += 1;
+                }
+            }
+            Some(b.len())
+        }
+        b'"' => Some(skip_quoted(b, i, b'"')),
+        b'\'' => match lang {
+            SrcLang::C => Some(skip_quoted(b, i, b'\'')),
+            SrcLang::Rust => {
+                if next == Some(b'\\') { return Some(skip_quoted(b, i, b'\'')) }
+                // char literal or lifetime?
+                let ch_len = src[i + 1..].chars().next()?.len_utf8();
+                if b.get(i + 1 + ch_len) == Some(&b'\'') { Some(i + 2 + ch_len) } else { None }
+            }
+        },
+        b'r' | b'b' if lang == SrcLang::Rust && (i == 0 || !is_ident_char(b[i - 1])) => {
+            // raw string: `r"..."`, `r#"..."#`, `br#"..."#`
+            let mut j = i + usize::from(b[i] == b'b');
+            if b.get(j) != Some(&b'r') { return None }
+            j += 1;
+            let hashes = b[j..].iter().take_while(|&&c|c == b'#').count();
+            j += hashes;
+            if b.get(j) != Some(&b'"') { return None }
+            let mut end = b"\"".to_vec();
+            end.extend(std::iter::repeat_n(b'#', hashes));
+            Some(b[j + 1..].windows(end.len()).position(|w|w == end).map(|p|j + 1 + p + end.len()).unwrap_or(b.len()))
+        }
+        b'#' if lang == SrcLang::C && b[..i].iter().rev().take_while(|&&c|c != b'\n').all(|c|c.is_ascii_whitespace()) => {
+            // preprocessor line (with `\` continuations)
+            let mut j = i;
+            while j < b.len() && !(b[j] == b'\n' && b[j - 1] != b'\\') {
+                j += 1;
+            }
+            Some(j)
+        }
+        _ => None,
+    }
+}
+
+fn skip_quoted(b: &[u8], i: usize, quote: u8) -> usize {
+    let mut j = i + 1;
+    while j < b.len() {
+        match b[j] {
+            b'\\' => j += 2,
+            c if c == quote => return j + 1,
+            _ => j += 1,
+        }
+    }
+    b.len()
+}
+
+/// index of the `}` that matches `{` at `i`
+fn matching_brace(src: &str, i: usize, lang: SrcLang) -> usize {
+    let b = src.as_bytes();
+    let mut depth = 0;
+    let mut j = i;
+    while j < b.len() {
+        if let Some(next) = skip_non_code(src, j, lang) {
+            j = next;
+            continue
+        }
+        match b[j] {
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 { return j }
+            }
+            _ => {}
+        }
+        j += 1;
+    }
+    b.len() - 1
+}
+
+/// keeps the source as is (header comment, items & their signatures)
+/// except function bodies that are replaced by a placeholder
+/// (`{ unimplemented!() }` for Rust & `{}` for C)
+pub fn src_stub(src: &str, lang: SrcLang) -> String {
+    let b = src.as_bytes();
+    let mut out = String::with_capacity(src.len() / 2);
+    let mut copied = 0;
+    let mut i = 0;
+
+    // depth of `(` & `[`
+    let mut nest = 0usize;
+    // Rust: `fn name` was met & its body is not yet
+    let mut fn_pending = false;
+    // C: last meaningful token is `)` of params (maybe with qualifiers after it)
+    let mut after_params = false;
+
+    while i < b.len() {
+        if let Some(next) = skip_non_code(src, i, lang) {
+            i = next;
+            continue
+        }
+
+        let c = b[i];
+        if is_ident_start(c) {
+            let len = b[i..].iter().take_while(|&&c|is_ident_char(c)).count();
+            let ident = &src[i..i + len];
+            match lang {
+                SrcLang::Rust => if ident == "fn" {
+                    let rest = &b[i + len..];
+                    let ws = rest.iter().take_while(|c|c.is_ascii_whitespace()).count();
+                    // `fn(..)` is a type, not an item
+                    fn_pending |= ws > 0 && rest.get(ws).map(|&c|is_ident_start(c)).unwrap_or(false);
+                },
+                SrcLang::C => after_params &= C_FN_QUALIFIERS.contains(&ident),
+            }
+            i += len;
+            continue
+        }
+
+        let is_body = match lang {
+            SrcLang::Rust => fn_pending,
+            SrcLang::C => after_params,
+        };
+        if !c.is_ascii_whitespace() {
+            after_params = false;
+        }
+
+        match c {
+            b'(' | b'[' => nest += 1,
+            b')' | b']' => {
+                nest = nest.saturating_sub(1);
+                after_params = c == b')' && nest == 0;
+            }
+            b';' if nest == 0 => fn_pending = false,
+            b'{' if nest == 0 && is_body => {
+                let end = matching_brace(src, i, lang);
+                out.push_str(&src[copied..i]);
+                out.push_str(lang.placeholder());
+                copied = end + 1;
+                i = end + 1;
+                fn_pending = false;
+                continue
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    out.push_str(&src[copied.min(src.len())..]);
+    out
+}
+
+
+/// `ClearFile` that keeps declarations of a source file but empties function bodies
+/// (see `fn src_stub`)
+///
+/// the language is chosen by the extension; files of unknown language
+/// (or non UTF-8 files) get the `fallback` content
+pub struct SrcStubF<'filter, S: AsRef<[u8]>> {
+    fallback: S,
+    file_filter: &'filter FileFilter,
+}
+
+impl<S: AsRef<[u8]>> SrcStubF<'static, S> {
+    pub fn new_no_filter(fallback: S) -> Self {
+        Self {
+            fallback,
+            file_filter: &FileFilter::EMPTY,
+        }
+    }
+}
+
+impl<'filter, S: AsRef<[u8]>> SrcStubF<'filter, S> {
+    pub fn new(fallback: S, file_filter: &'filter FileFilter) -> Self {
+        Self {
+            fallback,
+            file_filter,
+        }
+    }
+}
+
+impl<'filter, S: AsRef<[u8]>> ClearFile for SrcStubF<'filter, S> {
+    fn is_file_allow(&mut self, path: impl AsRef<Path>) -> bool {
+        self.file_filter.is_allowed_unchecked(path).unwrap_or(false)
+    }
+
+    fn clear_action(&mut self, f: &mut File, _: &Metadata) -> ResultIO {
+        rewrite(f, self.fallback.as_ref())
+    }
+
+    fn clear_path_action(&mut self, path: &Path, f: &mut File, md: &Metadata) -> ResultIO {
+        let Some(lang) = SrcLang::from_path(path) else { return self.clear_action(f, md) };
+        let Ok(src) = String::from_utf8(read_all(f)?) else { return self.clear_action(f, md) };
+        rewrite(f, src_stub(&src, lang).as_bytes())
+    }
+
+    fn is_truncate(&mut self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{src_stub, SrcLang};
+
+    #[test]
+    fn test_src_stub_rust() {
+        let src = r##"// SPDX-License-Identifier: MIT
+use std::fmt;
+
+pub struct S<'a> { f: fn(u8) -> u8, s: &'a str }
+
+impl<'a> S<'a> {
+    pub fn new(s: &'a str, arr: [u8; 2]) -> Self where Self: Sized {
+        let c = '}'; let r = r#"}"#; /* } /* } */ */
+        Self { f: |x| x, s }
+    }
+}
+
+trait T { fn decl(&self); fn def(&self) -> u8 { 1 } }
+"##;
+        let stub = r##"// SPDX-License-Identifier: MIT
+use std::fmt;
+
+pub struct S<'a> { f: fn(u8) -> u8, s: &'a str }
+
+impl<'a> S<'a> {
+    pub fn new(s: &'a str, arr: [u8; 2]) -> Self where Self: Sized { unimplemented!() }
+}
+
+trait T { fn decl(&self); fn def(&self) -> u8 { unimplemented!() } }
+"##;
+        assert_eq!(src_stub(src, SrcLang::Rust), stub);
+    }
+
+    #[test]
+    fn test_src_stub_c() {
+        let src = "/* License */\n#define M(x) { x }\nstruct s { int (*f)(int); };\n\
+            static int a[] = { 1, 2 };\nint f(int x) {\n  if (x) { return '}'; }\n  return 0;\n}\n\
+            class C { int g() const { return \"}\"[0]; } };\n";
+        let stub = "/* License */\n#define M(x) { x }\nstruct s { int (*f)(int); };\n\
+            static int a[] = { 1, 2 };\nint f(int x) {}\n\
+            class C { int g() const {} };\n";
+        assert_eq!(src_stub(src, SrcLang::C), stub);
+    }
+}
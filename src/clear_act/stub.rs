@@ -0,0 +1,258 @@
+use std::borrow::Cow;
+use std::path::Path;
+use std::fs::{File, Metadata};
+
+use super::{ResultIO, ClearFile};
+use super::util::{read_head, rewrite};
+use crate::filter::FileFilter;
+
+/// how many bytes of the original content are used for format sniffing
+const SNIFF_LEN: u64 = 64 * 1024;
+
+const EMPTY_ZIP: &[u8] = &[
+    0x50, 0x4b, 0x05, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+const EMPTY_TAR: &[u8] = &[0; 1024];
+const EMPTY_GZIP: &[u8] = &[
+    0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff,
+    0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+/// gzipped `EMPTY_TAR`
+const EMPTY_TAR_GZIP: &[u8] = &[
+    0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff,
+    0x63, 0x60, 0x18, 0x05, 0xa3, 0x60, 0x14, 0x8c, 0x54, 0x00, 0x00,
+    0x2e, 0xaf, 0xb5, 0xef, 0x00, 0x04, 0x00, 0x00,
+];
+
+/// format of a file for which a minimal valid ("empty") document is known
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StubFormat {
+    Json,
+    Toml,
+    Yaml,
+    Xml,
+    Zip,
+    Tar,
+    TarGzip,
+    Gzip,
+}
+
+impl StubFormat {
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let path = path.as_ref();
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        Some(match ext.as_str() {
+            "json" => Self::Json,
+            "toml" => Self::Toml,
+            "yaml" | "yml" => Self::Yaml,
+            "xml" => Self::Xml,
+            "zip" | "jar" => Self::Zip,
+            "tar" => Self::Tar,
+            "tgz" => Self::TarGzip,
+            "gz" if Path::new(path.file_stem()?).extension().is_some_and(|x|x.eq_ignore_ascii_case("tar")) => {
+                Self::TarGzip
+            }
+            "gz" => Self::Gzip,
+            _ => return None,
+        })
+    }
+
+    /// detects the format by the start of the content
+    ///
+    /// TOML & YAML can't be reliably sniffed so they are detected only by the path
+    pub fn sniff(head: &[u8]) -> Option<Self> {
+        if head.starts_with(&[0x1f, 0x8b]) { return Some(Self::Gzip) }
+        if head.starts_with(b"PK\x03\x04") || head.starts_with(b"PK\x05\x06") { return Some(Self::Zip) }
+        if head.get(257..262) == Some(b"ustar") { return Some(Self::Tar) }
+
+        match skip_bom_ws(head).first() {
+            Some(b'{' | b'[') => Some(Self::Json),
+            Some(b'<') => Some(Self::Xml),
+            _ => None,
+        }
+    }
+
+    /// minimal valid document of the format
+    ///
+    /// `head` is the start of the original content, it is used to keep the top-level
+    /// type of JSON & YAML and the root element name of XML
+    pub fn stub(self, head: &[u8]) -> Cow<'static, [u8]> {
+        match self {
+            Self::Json => match skip_bom_ws(head).first() {
+                Some(b'[') => Cow::Borrowed(b"[]\n"),
+                _ => Cow::Borrowed(b"{}\n"),
+            },
+            Self::Toml => Cow::Borrowed(b""),
+            Self::Yaml => if is_yaml_seq(head) {
+                Cow::Borrowed(b"[]\n")
+            } else {
+                Cow::Borrowed(b"{}\n")
+            },
+            Self::Xml => Cow::Owned(xml_stub(head)),
+            Self::Zip => Cow::Borrowed(EMPTY_ZIP),
+            Self::Tar => Cow::Borrowed(EMPTY_TAR),
+            Self::TarGzip => Cow::Borrowed(EMPTY_TAR_GZIP),
+            Self::Gzip => Cow::Borrowed(EMPTY_GZIP),
+        }
+    }
+}
+
+fn skip_bom_ws(text: &[u8]) -> &[u8] {
+    let text = text.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(text);
+    let start = text.iter().position(|b|!b.is_ascii_whitespace()).unwrap_or(text.len());
+    &text[start..]
+}
+
+/// is the first meaningful line of the YAML document a sequence item?
+fn is_yaml_seq(head: &[u8]) -> bool {
+    skip_bom_ws(head)
+        .split(|&b|b == b'\n')
+        .map(skip_bom_ws)
+        .find(|line|!line.is_empty() && !line.starts_with(b"#") && !line.starts_with(b"---"))
+        .map(|line|line == b"-" || line.starts_with(b"- "))
+        .unwrap_or(false)
+}
+
+/// `<root/>` with the root element name (and XML declaration) of the original
+fn xml_stub(head: &[u8]) -> Vec<u8> {
+    let text = skip_bom_ws(head);
+    let mut stub = Vec::new();
+
+    if text.starts_with(b"<?xml") {
+        if let Some(end) = find(text, b"?>") {
+            stub.extend_from_slice(&text[..end + 2]);
+            stub.push(b'\n');
+        }
+    }
+
+    stub.push(b'<');
+    stub.extend_from_slice(xml_root_name(text).unwrap_or(b"root"));
+    stub.extend_from_slice(b"/>\n");
+    stub
+}
+
+fn xml_root_name(mut text: &[u8]) -> Option<&[u8]> {
+    loop {
+        text = skip_bom_ws(text);
+        if text.starts_with(b"<?") {
+            text = &text[find(text, b"?>")? + 2..];
+        } else if text.starts_with(b"<!--") {
+            text = &text[find(text, b"-->")? + 3..];
+        } else if text.starts_with(b"<!") {
+            // doctype, maybe with internal subset: `<!DOCTYPE x [ ... ]>`
+            let mut depth = 0usize;
+            let end = text.iter().position(|&b| {
+                match b {
+                    b'[' => depth += 1,
+                    b']' => depth = depth.saturating_sub(1),
+                    b'>' if depth == 0 => return true,
+                    _ => {}
+                }
+                false
+            })?;
+            text = &text[end + 1..];
+        } else if text.starts_with(b"<") {
+            let name = &text[1..];
+            let len = name.iter()
+                .position(|&b|b.is_ascii_whitespace() || b == b'/' || b == b'>')
+                .unwrap_or(name.len());
+            return if len == 0 { None } else { Some(&name[..len]) }
+        } else {
+            return None
+        }
+    }
+}
+
+fn find(text: &[u8], pattern: &[u8]) -> Option<usize> {
+    text.windows(pattern.len()).position(|w|w == pattern)
+}
+
+
+/// `ClearFile` that change content of a file to the minimal valid document of its format
+///
+/// the format is chosen by the extension or (if there is no extension) sniffed by the content;
+/// files of unknown format (including unknown extensions) get the `fallback` content
+pub struct StubF<'filter, S: AsRef<[u8]>> {
+    fallback: S,
+    file_filter: &'filter FileFilter,
+}
+
+impl<S: AsRef<[u8]>> StubF<'static, S> {
+    pub fn new_no_filter(fallback: S) -> Self {
+        Self {
+            fallback,
+            file_filter: &FileFilter::EMPTY,
+        }
+    }
+}
+
+impl<'filter, S: AsRef<[u8]>> StubF<'filter, S> {
+    pub fn new(fallback: S, file_filter: &'filter FileFilter) -> Self {
+        Self {
+            fallback,
+            file_filter,
+        }
+    }
+
+    fn stub_action(&mut self, path: Option<&Path>, f: &mut File) -> ResultIO {
+        let head = read_head(f, SNIFF_LEN)?;
+        // unknown extensions are not sniffed: `[section]` of INI isn't JSON, HTML isn't XML
+        let format = match path {
+            Some(path) if path.extension().is_some() => StubFormat::from_path(path),
+            _ => StubFormat::sniff(&head),
+        };
+        match format {
+            Some(format) => rewrite(f, &format.stub(&head)),
+            None => rewrite(f, self.fallback.as_ref()),
+        }
+    }
+}
+
+impl<'filter, S: AsRef<[u8]>> ClearFile for StubF<'filter, S> {
+    fn is_file_allow(&mut self, path: impl AsRef<Path>) -> bool {
+        self.file_filter.is_allowed_unchecked(path).unwrap_or(false)
+    }
+
+    fn clear_action(&mut self, f: &mut File, _: &Metadata) -> ResultIO {
+        self.stub_action(None, f)
+    }
+
+    fn clear_path_action(&mut self, path: &Path, f: &mut File, _: &Metadata) -> ResultIO {
+        self.stub_action(Some(path), f)
+    }
+
+    fn is_truncate(&mut self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::StubFormat;
+
+    #[test]
+    fn test_stub_keeps_top_level() {
+        assert_eq!(&*StubFormat::Json.stub(b"  [1, 2]"), b"[]\n");
+        assert_eq!(&*StubFormat::Json.stub(b"{\"a\": 1}"), b"{}\n");
+        assert_eq!(&*StubFormat::Yaml.stub(b"# list\n- a\n- b\n"), b"[]\n");
+        assert_eq!(&*StubFormat::Yaml.stub(b"---\na: 1\n"), b"{}\n");
+
+        let xml = b"<?xml version=\"1.0\"?>\n<!-- c -->\n<!DOCTYPE cfg [<!ENTITY x \"y\">]>\n<cfg a=\"1\"><x/></cfg>";
+        assert_eq!(&*StubFormat::Xml.stub(xml), b"<?xml version=\"1.0\"?>\n<cfg/>\n");
+        assert_eq!(&*StubFormat::Xml.stub(b""), b"<root/>\n");
+    }
+
+    #[test]
+    fn test_sniff() {
+        assert_eq!(StubFormat::sniff(b"\xEF\xBB\xBF {}"), Some(StubFormat::Json));
+        assert_eq!(StubFormat::sniff(b"<a/>"), Some(StubFormat::Xml));
+        assert_eq!(StubFormat::sniff(b"\x1f\x8b\x08"), Some(StubFormat::Gzip));
+        assert_eq!(StubFormat::sniff(b"PK\x03\x04...."), Some(StubFormat::Zip));
+        assert_eq!(StubFormat::sniff(b"key = 1"), None);
+        assert_eq!(StubFormat::from_path("a/b.YML"), Some(StubFormat::Yaml));
+        assert_eq!(StubFormat::from_path("a/b.tar.gz"), Some(StubFormat::TarGzip));
+        assert_eq!(StubFormat::from_path("a/b.tgz"), Some(StubFormat::TarGzip));
+        assert_eq!(StubFormat::from_path("a/b.gz"), Some(StubFormat::Gzip));
+    }
+}
@@ -0,0 +1,88 @@
+use std::borrow::Cow;
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// BOM & dominant line ending of a text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextStyle {
+    /// `None` if the text is empty (so nothing is known about the BOM)
+    pub bom: Option<bool>,
+    /// * `Some(true)` if CRLF is the dominant line ending
+    /// * `Some(false)` if LF is the dominant line ending
+    /// * `None` if there are no line endings
+    pub crlf: Option<bool>,
+}
+
+impl TextStyle {
+    pub fn detect(text: &[u8]) -> Self {
+        let bom = if text.is_empty() { None } else { Some(text.starts_with(UTF8_BOM)) };
+
+        let mut crlf_count = 0usize;
+        let mut lf_count = 0usize;
+        for (i, _) in text.iter().enumerate().filter(|(_, &b)|b == b'\n') {
+            if i > 0 && text[i - 1] == b'\r' { crlf_count += 1 } else { lf_count += 1 }
+        }
+        let crlf = if crlf_count + lf_count == 0 { None } else { Some(crlf_count > lf_count) };
+
+        Self { bom, crlf }
+    }
+
+    /// normalizes BOM & line endings of `content` to the style
+    /// (unknown parts of the style keep the `content` as is)
+    pub fn apply<'a>(&self, content: &'a [u8]) -> Cow<'a, [u8]> {
+        let mut content = Cow::Borrowed(content);
+
+        match self.crlf {
+            Some(true) if content.windows(2).any(|w|w[0] != b'\r' && w[1] == b'\n') || content.first() == Some(&b'\n') => {
+                let mut crlf = Vec::with_capacity(content.len() + content.len() / 16);
+                for (i, &b) in content.iter().enumerate() {
+                    if b == b'\n' && (i == 0 || content[i - 1] != b'\r') { crlf.push(b'\r') }
+                    crlf.push(b);
+                }
+                content = Cow::Owned(crlf);
+            }
+            Some(false) if content.windows(2).any(|w|w == b"\r\n") => {
+                let mut lf = Vec::with_capacity(content.len());
+                for (i, &b) in content.iter().enumerate() {
+                    if b == b'\r' && content.get(i + 1) == Some(&b'\n') { continue }
+                    lf.push(b);
+                }
+                content = Cow::Owned(lf);
+            }
+            _ => {}
+        }
+
+        match self.bom {
+            Some(true) if !content.starts_with(UTF8_BOM) => {
+                let mut with_bom = UTF8_BOM.to_vec();
+                with_bom.extend_from_slice(&content);
+                content = Cow::Owned(with_bom);
+            }
+            Some(false) if content.starts_with(UTF8_BOM) => {
+                content = Cow::Owned(content[UTF8_BOM.len()..].to_vec());
+            }
+            _ => {}
+        }
+
+        content
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TextStyle;
+
+    #[test]
+    fn test_text_style() {
+        let style = TextStyle::detect(b"\xEF\xBB\xBFa\r\nb\r\nc\n");
+        assert_eq!(style, TextStyle { bom: Some(true), crlf: Some(true) });
+        assert_eq!(&*style.apply(b"x\ny\r\n"), b"\xEF\xBB\xBFx\r\ny\r\n");
+
+        let style = TextStyle::detect(b"a\nb\r\nc\n");
+        assert_eq!(style, TextStyle { bom: Some(false), crlf: Some(false) });
+        assert_eq!(&*style.apply(b"\xEF\xBB\xBFx\r\n"), b"x\n");
+
+        let style = TextStyle::detect(b"");
+        assert_eq!(&*style.apply(b"\xEF\xBB\xBFx\r\n"), b"\xEF\xBB\xBFx\r\n");
+    }
+}
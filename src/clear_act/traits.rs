@@ -1,32 +1,99 @@
-use std::path::Path;
-use std::fs::{File, Metadata};
+use std::path::{Path, PathBuf};
+use std::collections::HashMap;
+use std::fs::{File, Metadata, OpenOptions};
 use filetime::FileTime;
 
-use super::ResultIO;
+use crate::filter::{IgnoreFilter, IgnoreMode};
+use super::{ResultIO, ClearMode, ClearReport, ClearProgress, HardLinkGroup, HardLinkPolicy, open_no_atime};
+use super::atomic::atomic_clear;
 
 pub trait ClearFile {
     /// should the file be cleared?
     fn is_file_allow(&mut self, path: impl AsRef<Path>) -> bool;
     fn clear_action(&mut self, f: &mut File, md: &Metadata) -> ResultIO;
+    /// `clear_action` for actions that depends on the file path (e.g. on extension)
+    fn clear_path_action(&mut self, _path: &Path, f: &mut File, md: &Metadata) -> ResultIO {
+        self.clear_action(f, md)
+    }
+
+    /// should the file be truncated before `clear_action`?
+    /// 
+    /// if `false` the file is opened for read & write with the original content 
+    /// and `clear_action` is responsible for the final content and length  
+    fn is_truncate(&mut self) -> bool { true }
+
+    /// how the file should be cleared
+    /// 
+    /// in dir clearing multi-linked files follow `ClearDir::hard_link_policy` instead
+    fn clear_mode(&mut self) -> ClearMode { ClearMode::InPlace }
+
+    /// can the file be cleared atomically (`ClearMode::Atomic`)? 
+    /// 
+    /// `false` for actions that must overwrite the original data (e.g. shred passes): 
+    /// in a temp file they would leave the original blocks untouched
+    fn is_atomic_allowed(&mut self) -> bool { true }
+
+    /// if `true` allowed files are only reported as cleared but not opened or changed (preview)
+    fn is_dry_run(&mut self) -> bool { false }
+
+    /// is called with the dir whose files are cleared (the dir of `clear_dir_files_report` 
+    /// or the root of `clear_paths_report`) before its files & with `None` after them
+    fn on_clear_root(&mut self, _root: Option<&Path>) {}
 
     fn clear_file(&mut self, path: impl AsRef<Path>) -> ResultIO {
+        self.try_clear_file(path).map(|_|())
+    }
+
+    /// same as `clear_file`
+    /// # return
+    /// * `Ok(true)` if the file was cleared
+    /// * `Ok(false)` if the path is not a file or the file is not allowed
+    fn try_clear_file(&mut self, path: impl AsRef<Path>) -> std::io::Result<bool> {
+        let clear_mode = self.clear_mode();
+        self.try_clear_file_mode(path, clear_mode)
+    }
+
+    /// same as `try_clear_file` but with explicit `clear_mode`
+    fn try_clear_file_mode(&mut self, path: impl AsRef<Path>, clear_mode: ClearMode) -> std::io::Result<bool> {
         let path = path.as_ref();
                 
+        // metadata is captured strictly before any read of the file
         let md = std::fs::metadata(path)?;
-        if !md.is_file() { return Ok(()) }
+        if !md.is_file() { return Ok(false) }
         let mtime = FileTime::from_system_time(md.modified()?);
         let atime = FileTime::from_system_time(md.accessed()?);
     
-        if !self.is_file_allow(path) { return Ok(()) }
-
-        {
-            let mut f = File::create(path)?;
-            self.clear_action(&mut f, &md)?;
+        if !self.is_file_allow(path) { return Ok(false) }
+        if clear_mode == ClearMode::Atomic && !self.is_atomic_allowed() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput, 
+                "the action overwrites the original data so it can't clear atomically",
+            ))
         }
+        if self.is_dry_run() { return Ok(true) }
+
+        let result = match clear_mode {
+            ClearMode::InPlace => {
+                let f = if self.is_truncate() {
+                    File::create(path)
+                } else {
+                    open_no_atime(path, OpenOptions::new().read(true).write(true))
+                };
+                // the file isn't opened, so its times are unchanged
+                let mut f = f?;
+                self.clear_path_action(path, &mut f, &md)
+            }
+            ClearMode::Atomic => {
+                let truncate = self.is_truncate();
+                atomic_clear(path, &md, truncate, |f|self.clear_path_action(path, f, &md))
+            }
+        };
     
-        filetime::set_file_times(path, atime, mtime)?;
-    
-        Ok(())
+        // even if the action failed: it could have read (or partially changed) the file
+        let restored = filetime::set_file_times(path, atime, mtime);
+        // the error of the action is the cause, a restore error (e.g. `EPERM`) would hide it
+        result?;
+        restored.map(|_|true)
     }
 }
 
@@ -36,42 +103,263 @@ pub trait ClearDir: ClearFile {
     /// should the dir be cleared recursively?
     fn is_recursive(&mut self, dir_path: impl AsRef<Path>) -> bool;
 
-    fn clear_dir_files(&mut self, dir_path: impl AsRef<Path>) -> ResultIO
+    /// how deep the recursive clearing goes: `Some(0)` - only files of the dir itself,
+    /// `Some(1)` - files of its subdirs too, ..., `None` - no limit
+    fn max_depth(&mut self) -> Option<usize> { None }
+
+    /// should times (mtime & atime) of the dir be preserved while its files are cleared?
+    fn is_dir_times_preserve(&mut self, _dir_path: impl AsRef<Path>) -> bool { true }
+
+    /// what to do with files that have multiple hard links
+    fn hard_link_policy(&mut self) -> HardLinkPolicy { HardLinkPolicy::ClearOnce }
+
+    /// filter by ignore files (`.gitignore`, ...) that is applied in addition to other filters
+    fn ignore_filter(&mut self) -> Option<IgnoreFilter> { None }
+
+    /// is called after each file met in the traversal (`path`) with the progress of the current 
+    /// `clear_dir_files` / `clear_paths_report` call
+    fn on_progress(&mut self, _progress: &ClearProgress, _path: &Path) {}
+
+    /// is `on_progress` used? 
+    /// 
+    /// if `false` freed bytes are not counted (it costs 2 `metadata` calls per cleared file)
+    fn has_progress(&mut self) -> bool { false }
+
+    fn clear_dir_files(&mut self, dir_path: impl AsRef<Path>) -> ResultIO {
+        self.clear_dir_files_report(dir_path).map(|_|())
+    }
+
+    /// same as `clear_dir_files` but returns the report of cleared files
+    fn clear_dir_files_report(&mut self, dir_path: impl AsRef<Path>) -> std::io::Result<ClearReport>
     {
         let dir_path = dir_path.as_ref();
-        if !self.is_dir_allow(dir_path) { return Ok(()) }
+        self.on_clear_root(Some(dir_path));
+        let report = clear_dir_report(self, dir_path);
+        self.on_clear_root(None);
+        report
+    }
 
-        let mut rec_dirs = vec![];
-        let mut first = true;
-    
-        // loop for recursive dir traversal
-        'rec: loop {
-            let dir_elems = if first {
-                std::fs::read_dir(dir_path)
-            } else {
-                let dir_path = rec_dirs.pop();
-                if let Some(dir_path) = dir_path { 
-                    if !self.is_dir_allow(&dir_path) { continue 'rec } 
-                    std::fs::read_dir(dir_path) 
-                } else {
-                    break 'rec
+    /// clears the listed files (e.g. from `git` or a file with paths) of the `root` dir
+    /// 
+    /// files are filtered as in `clear_dir_files` of the `root`: a file is skipped if the root 
+    /// or any dir between the root and the file is not allowed 
+    /// (only the parent dir is checked for a file outside of the root)
+    fn clear_paths_report<P: AsRef<Path>>(
+        &mut self, 
+        root: impl AsRef<Path>, 
+        paths: impl IntoIterator<Item = P>,
+    ) -> std::io::Result<ClearReport> {
+        let root = root.as_ref();
+        let mut report = ClearReport::default();
+        let mut hard_links = HardLinks::new(self.hard_link_policy());
+        let mut progress = ClearProgress::default();
+        let mut dirs_allow = HashMap::new();
+
+        self.on_clear_root(Some(root));
+        let result: ResultIO = (|| {
+            for path in paths {
+                let path = path.as_ref();
+                if !path.is_file() { continue }
+                progress.visited += 1;
+
+                let parent = path.parent().filter(|x|!x.as_os_str().is_empty());
+                let allowed = parent.is_none_or(|parent| {
+                    if !parent.starts_with(root) { return self.is_dir_allow(parent) }
+                    parent.ancestors()
+                        .take_while(|dir|dir.starts_with(root))
+                        .all(|dir|*dirs_allow.entry(dir.to_path_buf()).or_insert_with(||self.is_dir_allow(dir)))
+                });
+                if allowed {
+                    clear_file_progress(self, &mut hard_links, path, &mut report, &mut progress)?;
                 }
-            }?;
-    
-            for dir_elem in dir_elems {
+                self.on_progress(&progress, path);
+            }
+            Ok(())
+        })();
+        self.on_clear_root(None);
+
+        result.map(|_|report)
+    }
+}
+
+/// `ClearDir::clear_dir_files_report` (without `ClearFile::on_clear_root` calls)
+fn clear_dir_report<C: ClearDir + ?Sized>(clearer: &mut C, dir_path: &Path) -> std::io::Result<ClearReport> {
+    let mut report = ClearReport::default();
+    if !clearer.is_dir_allow(dir_path) { return Ok(report) }
+
+    let mut hard_links = HardLinks::new(clearer.hard_link_policy());
+    let mut progress = ClearProgress::default();
+
+    let ignore_filter = clearer.ignore_filter();
+    let ignore_level = match &ignore_filter {
+        Some(filter) => Some(filter.start_level(dir_path)?),
+        None => None,
+    };
+    let max_depth = clearer.max_depth();
+    let mut rec_dirs = vec![(dir_path.to_path_buf(), ignore_level, 0)];
+    let mut restore_err = None;
+
+    // loop for recursive dir traversal
+    while let Some((dir_path, ignore_level, depth)) = rec_dirs.pop() {
+        // captured before `read_dir` that can bump atime
+        let times = if clearer.is_dir_times_preserve(&dir_path) {
+            let md = std::fs::metadata(&dir_path)?;
+            Some((FileTime::from_last_access_time(&md), FileTime::from_last_modification_time(&md)))
+        } else {
+            None
+        };
+
+        let result: ResultIO = (|| {
+            for dir_elem in std::fs::read_dir(&dir_path)? {
                 let dir_elem = dir_elem?;
                 let path = dir_elem.path();
+                let name = dir_elem.file_name();
                 
-                if path.is_dir() && self.is_recursive(&path) { 
-                    rec_dirs.push(path)
-                } else if path.is_file() { 
-                    self.clear_file(path)?;
+                if path.is_dir() && max_depth.is_none_or(|max|depth < max) && clearer.is_recursive(&path) { 
+                    let child_level = match (&ignore_filter, &ignore_level) {
+                        (Some(filter), Some(level)) => {
+                            if name == ".git" { continue }
+                            let child = level.child(filter, &name)?;
+                            // nothing can be unignored inside of an ignored dir
+                            if child.is_dir_ignored() && filter.mode() == IgnoreMode::Unignored { continue }
+                            Some(child)
+                        }
+                        _ => None,
+                    };
+                    if clearer.is_dir_allow(&path) { rec_dirs.push((path, child_level, depth + 1)) }
+                    continue
+                } 
+                if !path.is_file() { continue }
+
+                let allowed = match (&ignore_filter, &ignore_level) {
+                    (Some(filter), Some(level)) => filter.is_allowed(level.is_ignored(&name, false)),
+                    _ => true,
+                };
+                progress.visited += 1;
+                if allowed {
+                    clear_file_progress(clearer, &mut hard_links, &path, &mut report, &mut progress)?;
                 }
+                clearer.on_progress(&progress, &path);
             }
-    
-            first = false;
+            Ok(())
+        })();
+
+        let restored = times.map_or(Ok(()), |times|restore_dir_times(&dir_path, times));
+        result?;
+        // the rest of dirs are cleared even if times of this one can't be restored
+        if let Err(err) = restored {
+            restore_err.get_or_insert(err);
+        }
+    }
+
+    match restore_err {
+        Some(err) => Err(err),
+        None => Ok(report),
+    }
+}
+
+/// clearing of files with respect to `HardLinkPolicy`
+struct HardLinks {
+    policy: HardLinkPolicy,
+    /// (dev, ino) => index in `ClearReport::hard_links`
+    groups: HashMap<(u64, u64), usize>,
+}
+
+impl HardLinks {
+    fn new(policy: HardLinkPolicy) -> Self {
+        Self { policy, groups: HashMap::new() }
+    }
+
+    fn clear_file<C: ClearFile + ?Sized>(&mut self, clearer: &mut C, path: PathBuf, report: &mut ClearReport) -> ResultIO {
+        let Some((inode, nlink)) = hard_link_inode(&path)? else {
+            if clearer.try_clear_file(&path)? { report.cleared.push(path) }
+            return Ok(())
+        };
+
+        let group_idx = *self.groups.entry(inode).or_insert_with(|| {
+            report.hard_links.push(HardLinkGroup { paths: vec![], nlink, cleared: false });
+            report.hard_links.len() - 1
+        });
+        let group = &mut report.hard_links[group_idx];
+        group.paths.push(path.clone());
+
+        let cleared = match self.policy {
+            HardLinkPolicy::ClearOnce if !group.cleared => {
+                clearer.try_clear_file_mode(&path, ClearMode::InPlace)?
+            }
+            HardLinkPolicy::ClearOnce | HardLinkPolicy::Skip => false,
+            HardLinkPolicy::BreakLink => clearer.try_clear_file_mode(&path, ClearMode::Atomic)?,
+        };
+        if cleared {
+            group.cleared = true;
+            report.cleared.push(path);
         }
-    
         Ok(())
     }
 }
+
+/// clears the file (see `HardLinks::clear_file`) & counts it in the `progress`
+fn clear_file_progress<C: ClearDir + ?Sized>(
+    clearer: &mut C, 
+    hard_links: &mut HardLinks, 
+    path: &Path, 
+    report: &mut ClearReport, 
+    progress: &mut ClearProgress,
+) -> ResultIO {
+    // nothing is freed in the dry run
+    let count_freed = clearer.has_progress() && !clearer.is_dry_run();
+    let size = |path: &Path| std::fs::metadata(path).map(|md|allocated_size(&md)).unwrap_or(0);
+    let old_size = if count_freed { size(path) } else { 0 };
+    let cleared = report.cleared.len();
+    match hard_links.clear_file(clearer, path.to_path_buf(), report) {
+        // the content doesn't fit the action, the rest of files can still be cleared
+        Err(err) if err.kind() == std::io::ErrorKind::InvalidData => report.invalid.push(path.to_path_buf()),
+        result => result?,
+    }
+
+    if report.cleared.len() > cleared {
+        progress.cleared += 1;
+        if count_freed {
+            progress.freed_bytes += old_size.saturating_sub(size(path));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn allocated_size(md: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    md.blocks() * 512
+}
+
+#[cfg(not(unix))]
+fn allocated_size(md: &Metadata) -> u64 {
+    md.len()
+}
+
+/// # return
+/// * `Some(((dev, ino), nlink))` if the file has multiple hard links
+/// * `None` if the file has one link (or it is unknown)
+#[cfg(unix)]
+fn hard_link_inode(path: &Path) -> std::io::Result<Option<((u64, u64), u64)>> {
+    use std::os::unix::fs::MetadataExt;
+    let md = std::fs::metadata(path)?;
+    Ok((md.nlink() > 1).then(||((md.dev(), md.ino()), md.nlink())))
+}
+
+#[cfg(not(unix))]
+fn hard_link_inode(_: &Path) -> std::io::Result<Option<((u64, u64), u64)>> {
+    Ok(None)
+}
+
+/// sets `(atime, mtime)` of the dir only if they were changed
+/// (so dirs of other owners that were not changed are not an error)
+fn restore_dir_times(dir_path: &Path, (atime, mtime): (FileTime, FileTime)) -> ResultIO {
+    let md = std::fs::metadata(dir_path)?;
+    let cur_atime = FileTime::from_last_access_time(&md);
+    let cur_mtime = FileTime::from_last_modification_time(&md);
+    if (cur_atime, cur_mtime) != (atime, mtime) {
+        filetime::set_file_times(dir_path, atime, mtime)?;
+    }
+    Ok(())
+}
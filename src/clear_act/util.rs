@@ -0,0 +1,27 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use super::ResultIO;
+
+/// reads at most `max_len` bytes from the start of the file
+/// 
+/// the position is restored to the start
+pub(crate) fn read_head(f: &mut File, max_len: u64) -> std::io::Result<Vec<u8>> {
+    let mut head = Vec::new();
+    f.seek(SeekFrom::Start(0))?;
+    Read::by_ref(f).take(max_len).read_to_end(&mut head)?;
+    f.seek(SeekFrom::Start(0))?;
+    Ok(head)
+}
+
+/// reads the whole file, the position is restored to the start
+pub(crate) fn read_all(f: &mut File) -> std::io::Result<Vec<u8>> {
+    read_head(f, u64::MAX)
+}
+
+/// replaces the whole content of the file by `content`
+pub(crate) fn rewrite(f: &mut File, content: &[u8]) -> ResultIO {
+    f.set_len(0)?;
+    f.seek(SeekFrom::Start(0))?;
+    f.write_all(content)
+}
@@ -0,0 +1,188 @@
+use std::path::Path;
+use std::fs::{File, Metadata};
+
+use super::{ResultIO, ClearFile, ClearDir, ClearMode, ClearProgress, HardLinkPolicy};
+use crate::filter::{DirFilter, IgnoreFilter};
+use crate::guard::PathGuard;
+
+
+/// decision about a file allowed by filters (see `WrapD::set_file_confirm`)
+pub type FileConfirm<'a> = Box<dyn FnMut(&Path) -> bool + 'a>;
+
+/// progress callback (see `ClearDir::on_progress`)
+pub type ProgressFn<'a> = Box<dyn FnMut(&ClearProgress, &Path) + 'a>;
+
+/// `ClearDir` that clears files of a dir by any `ClearFile` action
+/// 
+/// by default is not recursive  
+pub struct WrapD<'filter, F: ClearFile> {
+    file_act: F,
+    dir_filter: &'filter DirFilter,
+    recursive: bool,
+    max_depth: Option<usize>,
+    clear_mode: ClearMode,
+    hard_link_policy: HardLinkPolicy,
+    ignore_filter: Option<&'filter IgnoreFilter>,
+    path_guard: Option<&'filter PathGuard>,
+    dry_run: bool,
+    file_confirm: Option<FileConfirm<'filter>>,
+    progress: Option<ProgressFn<'filter>>,
+}
+
+impl<F: ClearFile> WrapD<'static, F> {
+    pub fn wrap_no_filter(file_act: F) -> Self {
+        Self {
+            file_act,
+            dir_filter: &DirFilter::EMPTY,
+            recursive: false,
+            max_depth: None,
+            clear_mode: ClearMode::InPlace,
+            hard_link_policy: HardLinkPolicy::ClearOnce,
+            ignore_filter: None,
+            path_guard: None,
+            dry_run: false,
+            file_confirm: None,
+            progress: None,
+        }
+    }
+}
+
+impl<'filter, F: ClearFile> WrapD<'filter, F> {
+    pub fn wrap(file_act: F, dir_filter: &'filter DirFilter) -> Self {
+        Self {
+            file_act,
+            dir_filter,
+            recursive: false,
+            max_depth: None,
+            clear_mode: ClearMode::InPlace,
+            hard_link_policy: HardLinkPolicy::ClearOnce,
+            ignore_filter: None,
+            path_guard: None,
+            dry_run: false,
+            file_confirm: None,
+            progress: None,
+        }
+    }
+
+    pub fn set_recursive(&mut self, recursive: bool) {
+        self.recursive = recursive
+    }
+
+    /// limit of the recursive clearing (see `ClearDir::max_depth`)
+    pub fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.max_depth = max_depth
+    }
+
+    pub fn set_clear_mode(&mut self, clear_mode: ClearMode) {
+        self.clear_mode = clear_mode
+    }
+
+    pub fn set_hard_link_policy(&mut self, hard_link_policy: HardLinkPolicy) {
+        self.hard_link_policy = hard_link_policy
+    }
+
+    pub fn set_ignore_filter(&mut self, ignore_filter: Option<&'filter IgnoreFilter>) {
+        self.ignore_filter = ignore_filter
+    }
+
+    /// protected files & dirs (see `PathGuard`) are skipped
+    pub fn set_path_guard(&mut self, path_guard: Option<&'filter PathGuard>) {
+        self.path_guard = path_guard
+    }
+
+    /// matched files are only reported (see `ClearFile::is_dry_run`)
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run
+    }
+
+    /// `file_confirm` is asked about each file allowed by filters right before its clearing
+    /// (e.g. an interactive prompt), `false` skips the file
+    pub fn set_file_confirm(&mut self, file_confirm: Option<FileConfirm<'filter>>) {
+        self.file_confirm = file_confirm
+    }
+
+    pub fn set_progress(&mut self, progress: Option<ProgressFn<'filter>>) {
+        self.progress = progress
+    }
+
+    fn is_guard_allow(&self, path: &Path) -> bool {
+        self.path_guard.is_none_or(|guard|guard.is_allowed(path))
+    }
+
+    pub fn file_act(&self) -> &F {
+        &self.file_act
+    }
+
+    pub fn file_act_mut(&mut self) -> &mut F {
+        &mut self.file_act
+    }
+}
+
+impl<'filter, F: ClearFile> ClearFile for WrapD<'filter, F> {
+    fn is_file_allow(&mut self, path: impl AsRef<Path>) -> bool {
+        let path = path.as_ref();
+        self.is_guard_allow(path) 
+            && self.file_act.is_file_allow(path) 
+            && self.file_confirm.as_mut().is_none_or(|confirm|confirm(path))
+    }
+
+    fn clear_action(&mut self, f: &mut File, md: &Metadata) -> ResultIO {
+        self.file_act.clear_action(f, md)
+    }
+
+    fn clear_path_action(&mut self, path: &Path, f: &mut File, md: &Metadata) -> ResultIO {
+        self.file_act.clear_path_action(path, f, md)
+    }
+
+    fn is_truncate(&mut self) -> bool {
+        self.file_act.is_truncate()
+    }
+
+    fn clear_mode(&mut self) -> ClearMode {
+        self.clear_mode
+    }
+
+    fn is_atomic_allowed(&mut self) -> bool {
+        self.file_act.is_atomic_allowed()
+    }
+
+    fn is_dry_run(&mut self) -> bool {
+        self.dry_run
+    }
+
+    fn on_clear_root(&mut self, root: Option<&Path>) {
+        self.file_act.on_clear_root(root)
+    }
+}
+
+impl<'filter, F: ClearFile> ClearDir for WrapD<'filter, F> {
+    fn is_dir_allow(&mut self, dir_path: impl AsRef<Path>) -> bool {
+        self.is_guard_allow(dir_path.as_ref()) && self.dir_filter.is_allowed_unchecked(dir_path)
+    }
+
+    fn is_recursive(&mut self, _: impl AsRef<Path>) -> bool {
+        self.recursive
+    }
+
+    fn max_depth(&mut self) -> Option<usize> {
+        self.max_depth
+    }
+
+    fn hard_link_policy(&mut self) -> HardLinkPolicy {
+        self.hard_link_policy
+    }
+
+    fn ignore_filter(&mut self) -> Option<IgnoreFilter> {
+        self.ignore_filter.cloned()
+    }
+
+    fn on_progress(&mut self, progress: &ClearProgress, path: &Path) {
+        if let Some(on_progress) = &mut self.progress {
+            on_progress(progress, path)
+        }
+    }
+
+    fn has_progress(&mut self) -> bool {
+        self.progress.is_some()
+    }
+}
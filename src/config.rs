@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+/// named set of clearing options
+///
+/// keys are the long CLI option names (`white-list` & co are `--wlr`, `--blr` & `--dblr`);
+/// relative paths are relative to the dir of the config file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Profile {
+    // roots
+    pub files: Vec<PathBuf>,
+    pub dirs: Vec<PathBuf>,
+    pub recursive: bool,
+    pub max_depth: Option<usize>,
+
+    // filters
+    pub white_list: Option<String>,
+    pub black_list: Option<String>,
+    pub dir_black_list: Option<String>,
+    pub gitignore: bool,
+    pub only_ignored: bool,
+    pub ignore_file: Vec<String>,
+    pub git: Option<String>,
+    pub git_since: Option<String>,
+    pub hard_links: Option<String>,
+
+    // content
+    pub new_content: Option<String>,
+    pub content_file: Option<PathBuf>,
+    pub match_style: bool,
+    pub fill: Option<String>,
+    pub fill_zero: bool,
+    pub shred: Vec<String>,
+    pub punch_hole: bool,
+    pub stub: bool,
+    pub json_skeleton: bool,
+    pub src_stub: bool,
+    pub dummy: Option<String>,
+    pub seed: Option<u64>,
+    pub keep_lines: bool,
+    pub atomic: bool,
+    pub git_index: Option<String>,
+
+    // safety
+    pub root: Option<PathBuf>,
+    pub protect: Vec<PathBuf>,
+    pub allow_dangerous: bool,
+}
+
+impl Profile {
+    /// max depth of dirs (see `ClearDir::max_depth`): `max-depth`, or no limit if `recursive`,
+    /// or `Some(0)` (non-recursive)
+    pub fn dir_depth(&self) -> Option<usize> {
+        match self.max_depth {
+            Some(depth) => Some(depth),
+            None if self.recursive => None,
+            None => Some(0),
+        }
+    }
+
+    fn resolve_paths(&mut self, base: &Path) {
+        let paths = self.files.iter_mut()
+            .chain(&mut self.dirs)
+            .chain(&mut self.content_file)
+            .chain(&mut self.root)
+            .chain(&mut self.protect);
+        for path in paths {
+            if path.is_relative() {
+                *path = base.join(&*path);
+            }
+        }
+    }
+}
+
+/// TOML config with profiles as `[profile.NAME]` tables
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub profile: BTreeMap<String, Profile>,
+}
+
+impl Config {
+    /// name of the project-local config
+    pub const PROJECT_FILE: &'static str = ".cfpm.toml";
+
+    /// relative paths of profiles stay as is
+    pub fn parse(text: &str) -> std::io::Result<Self> {
+        toml::from_str(text).map_err(|err|std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = path.as_ref();
+        let mut config = Self::parse(&std::fs::read_to_string(path)?).map_err(|err| {
+            std::io::Error::new(err.kind(), format!("config {path:?}: {err}"))
+        })?;
+
+        let base = path.parent().filter(|x|!x.as_os_str().is_empty()).unwrap_or(".".as_ref());
+        for profile in config.profile.values_mut() {
+            profile.resolve_paths(base);
+        }
+        Ok(config)
+    }
+
+    /// default config paths by priority:
+    /// the project-local config (the nearest `.cfpm.toml` in `dir` or its ancestors) &
+    /// the user-level config (`$XDG_CONFIG_HOME/cfpm/config.toml` or `~/.config/cfpm/config.toml`)
+    pub fn default_paths(dir: impl AsRef<Path>) -> Vec<PathBuf> {
+        let mut paths = vec![];
+        if let Ok(dir) = std::path::absolute(dir) {
+            let project = dir.ancestors()
+                .map(|dir|dir.join(Self::PROJECT_FILE))
+                .find(|path|path.is_file());
+            paths.extend(project);
+        }
+
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME").map(PathBuf::from)
+            .or_else(||std::env::var_os("HOME").map(|home|Path::new(&home).join(".config")))
+            .or_else(||std::env::var_os("APPDATA").map(PathBuf::from));
+        paths.extend(config_dir.map(|dir|dir.join("cfpm").join("config.toml")));
+        paths
+    }
+}
+
+/// the profile from the first of the configs that has it (missing configs are skipped)
+pub fn find_profile<P: AsRef<Path>>(
+    config_paths: impl IntoIterator<Item = P>,
+    name: &str,
+) -> std::io::Result<Option<Profile>> {
+    for path in config_paths {
+        let mut config = match Config::read(path) {
+            Ok(config) => config,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        };
+        if let Some(profile) = config.profile.remove(name) {
+            return Ok(Some(profile))
+        }
+    }
+    Ok(None)
+}
@@ -40,7 +40,7 @@ impl FileFilter {
     /// * `Some(false)` if the file denied
     /// * `None` if there no file name in the path
     pub fn is_allowed_unchecked(&self, path: impl AsRef<std::path::Path>) -> Option<bool> {
-        let Some(path) = path.as_ref().file_name() else { return None };
+        let path = path.as_ref().file_name()?;
         Some(self.0.is_allowed_opt(path.to_str()))
     }
 
@@ -0,0 +1,175 @@
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+
+use crate::clear_act::open_read_no_atime;
+
+/// which files are allowed by `IgnoreFilter`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IgnoreMode {
+    /// only files that are not ignored (e.g. tracked sources)
+    Unignored,
+    /// only ignored files (e.g. build products)
+    Ignored,
+}
+
+/// filter by ignore files (`.gitignore`, `.ignore`, `.cfpmignore`) with nested gitignore semantics:
+/// rules of deeper dirs take precedence, negation (`!`) & anchoring (`/`) are supported
+/// and nothing inside an ignored dir can be re-included
+///
+/// in the same dir rules of later ignore files take precedence;
+/// for a git repo `.git/info/exclude` & ignore files of dirs up to the repo root are used too
+///
+/// `.git` dirs are never traversed while the filter is used
+#[derive(Debug, Clone)]
+pub struct IgnoreFilter {
+    file_names: Vec<OsString>,
+    mode: IgnoreMode,
+}
+
+impl IgnoreFilter {
+    pub const TOOL_IGNORE_FILE: &'static str = ".cfpmignore";
+
+    pub fn new(mode: IgnoreMode) -> Self {
+        Self {
+            file_names: [".gitignore", ".ignore", Self::TOOL_IGNORE_FILE].map(OsString::from).to_vec(),
+            mode,
+        }
+    }
+
+    /// adds one more ignore file name (with the highest precedence)
+    pub fn add_file_name(&mut self, name: impl Into<OsString>) {
+        self.file_names.push(name.into())
+    }
+
+    pub fn mode(&self) -> IgnoreMode {
+        self.mode
+    }
+
+    /// # params
+    /// * `ignored`: is the file ignored (see `IgnoreLevel::is_ignored`)
+    pub fn is_allowed(&self, ignored: bool) -> bool {
+        match self.mode {
+            IgnoreMode::Unignored => !ignored,
+            IgnoreMode::Ignored => ignored,
+        }
+    }
+
+    /// rules of ignore files in the `dir` (`dir` must be absolute)
+    fn dir_rules(&self, dir: &Path) -> std::io::Result<Option<Gitignore>> {
+        let mut builder = GitignoreBuilder::new(dir);
+        let mut is_empty = true;
+
+        let exclude = dir.join(".git").join("info").join("exclude");
+        let ignore_files = self.file_names.iter().map(|name|dir.join(name));
+        for path in std::iter::once(exclude).chain(ignore_files) {
+            let content = match open_read_no_atime(&path) {
+                Ok(mut f) => {
+                    let mut content = String::new();
+                    std::io::Read::read_to_string(&mut f, &mut content)?;
+                    content
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err),
+            };
+            for line in content.lines() {
+                builder.add_line(Some(path.clone()), line).map_err(std::io::Error::other)?;
+                is_empty = false;
+            }
+        }
+
+        if is_empty { return Ok(None) }
+        builder.build().map(Some).map_err(std::io::Error::other)
+    }
+
+    /// level of the traversal start dir: with rules of dirs up to the git repo root
+    pub fn start_level(&self, dir: impl AsRef<Path>) -> std::io::Result<Rc<IgnoreLevel>> {
+        let dir = std::fs::canonicalize(dir)?;
+
+        // dirs up to the repo root (if any)
+        let mut ancestors = vec![];
+        if !dir.join(".git").exists() {
+            for ancestor in dir.ancestors().skip(1) {
+                ancestors.push(ancestor);
+                if ancestor.join(".git").exists() { break }
+            }
+            if !ancestors.last().map(|x|x.join(".git").exists()).unwrap_or(false) {
+                ancestors.clear()
+            }
+        }
+
+        let mut level: Option<Rc<IgnoreLevel>> = None;
+        for ancestor in ancestors.into_iter().rev() {
+            let ignored = level.as_ref()
+                .map(|level|level.is_ignored(ancestor.file_name().unwrap_or_default(), true))
+                .unwrap_or(false);
+            level = Some(Rc::new(IgnoreLevel {
+                rules: self.dir_rules(ancestor)?,
+                dir: ancestor.to_path_buf(),
+                ignored,
+                parent: level,
+            }));
+        }
+
+        let ignored = level.as_ref()
+            .map(|level|level.is_ignored(dir.file_name().unwrap_or_default(), true))
+            .unwrap_or(false);
+        Ok(Rc::new(IgnoreLevel {
+            rules: self.dir_rules(&dir)?,
+            dir,
+            ignored,
+            parent: level,
+        }))
+    }
+}
+
+/// ignore rules of a dir in the traversal (with rules of its parents)
+pub struct IgnoreLevel {
+    dir: PathBuf,
+    rules: Option<Gitignore>,
+    /// the dir itself is ignored, so everything in it is ignored
+    ignored: bool,
+    parent: Option<Rc<IgnoreLevel>>,
+}
+
+impl IgnoreLevel {
+    /// is the entry `name` of the dir ignored?
+    pub fn is_ignored(&self, name: &OsStr, is_dir: bool) -> bool {
+        if self.ignored { return true }
+
+        let path = self.dir.join(name);
+        let mut level = Some(self);
+        while let Some(cur) = level {
+            if let Some(rules) = &cur.rules {
+                match rules.matched(&path, is_dir) {
+                    Match::Ignore(_) => return true,
+                    Match::Whitelist(_) => return false,
+                    Match::None => {}
+                }
+            }
+            level = cur.parent.as_deref();
+        }
+        false
+    }
+
+    /// level of the subdir `name` of the dir
+    pub fn child(self: &Rc<Self>, filter: &IgnoreFilter, name: &OsStr) -> std::io::Result<Rc<Self>> {
+        let dir = self.dir.join(name);
+        let ignored = self.is_ignored(name, true);
+        Ok(Rc::new(Self {
+            // rules can't re-include anything inside of an ignored dir
+            rules: if ignored { None } else { filter.dir_rules(&dir)? },
+            ignored,
+            dir,
+            parent: Some(self.clone()),
+        }))
+    }
+
+    /// is the dir itself ignored?
+    pub fn is_dir_ignored(&self) -> bool {
+        self.ignored
+    }
+}
@@ -4,4 +4,6 @@ pub use wb_filter::WbFilter;
 mod file_filter;
 pub use file_filter::FileFilter;
 mod dir_filter;
-pub use dir_filter::DirFilter;
\ No newline at end of file
+pub use dir_filter::DirFilter;
+mod ignore_filter;
+pub use ignore_filter::{IgnoreFilter, IgnoreLevel, IgnoreMode};
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::path_list::split_paths;
+
+/// which files of a git working tree are selected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GitSelect {
+    /// files in the index
+    Tracked,
+    /// files that are not in the index & not ignored
+    Untracked,
+    /// tracked files that differ from `HEAD` (deleted files are skipped)
+    Modified,
+    /// files that differ from the commit (deleted files are skipped)
+    ChangedSince(String),
+}
+
+/// runs local `git` (no network access) in `dir` & returns its stdout
+pub(crate) fn git_output(dir: &Path, args: &[&str]) -> std::io::Result<Vec<u8>> {
+    let output = Command::new("git").arg("-C").arg(dir).args(args).output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(std::io::Error::other(format!("`git {}` failed: {}", args.join(" "), stderr.trim())))
+    }
+    Ok(output.stdout)
+}
+
+/// paths of the selected files in the `dir` (recursively) of a git working tree
+/// 
+/// the paths are `dir` joined with paths relative to it
+pub fn git_paths(dir: impl AsRef<Path>, select: &GitSelect) -> std::io::Result<Vec<PathBuf>> {
+    let dir = dir.as_ref();
+    let output = match select {
+        GitSelect::Tracked => git_output(dir, &["ls-files", "-z"])?,
+        GitSelect::Untracked => git_output(dir, &["ls-files", "-z", "--others", "--exclude-standard"])?,
+        GitSelect::Modified => git_output(dir, &["diff", "--name-only", "-z", "--relative", "--diff-filter=d", "HEAD", "--"])?,
+        GitSelect::ChangedSince(commit) => {
+            let commit = resolve_commit(dir, commit)?;
+            git_output(dir, &["diff", "--name-only", "-z", "--relative", "--diff-filter=d", &commit, "--"])?
+        }
+    };
+    Ok(split_paths(&output, b'\0').into_iter().map(|path|dir.join(path)).collect())
+}
+
+/// full hash of the `commit` (a hash, ref, `HEAD~2`, ...)
+/// 
+/// `--end-of-options` makes git read a `commit` that starts with `-` as a revision, not as an option
+fn resolve_commit(dir: &Path, commit: &str) -> std::io::Result<String> {
+    let rev = format!("{commit}^{{commit}}");
+    let output = git_output(dir, &["rev-parse", "--verify", "--quiet", "--end-of-options", &rev])
+        .map_err(|_|std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("{commit:?} is not a commit")))?;
+    Ok(String::from_utf8_lossy(&output).trim().to_string())
+}
+
+/// root of the git working tree that contains the `path` (the nearest dir with `.git`), canonicalized
+/// 
+/// unlike `git rev-parse --show-toplevel` it doesn't spawn git, so it is cheap for each of many files
+pub fn work_tree_root(path: impl AsRef<Path>) -> Option<PathBuf> {
+    let path = std::fs::canonicalize(path).ok()?;
+    path.ancestors()
+        .find(|dir|dir.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+/// how cleared tracked files are marked in the git index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexMark {
+    /// `git update-index --assume-unchanged`
+    AssumeUnchanged,
+    /// `git update-index --skip-worktree`
+    SkipWorktree,
+}
+
+/// paths (relative to `dir`) of the files that are tracked by git in the working tree of `dir`
+/// 
+/// non-existing & untracked paths are skipped
+pub fn tracked_paths<P: AsRef<Path>>(
+    dir: impl AsRef<Path>, 
+    paths: impl IntoIterator<Item = P>,
+) -> std::io::Result<Vec<PathBuf>> {
+    let dir = dir.as_ref();
+    let canonical_dir = std::fs::canonicalize(dir)?;
+
+    let tracked: HashMap<PathBuf, PathBuf> = split_paths(&git_output(dir, &["ls-files", "-z"])?, b'\0')
+        .into_iter()
+        .map(|rel|(canonical_dir.join(&rel), rel))
+        .collect();
+
+    Ok(paths.into_iter()
+        .filter_map(|path|std::fs::canonicalize(path).ok())
+        .filter_map(|path|tracked.get(&path).cloned())
+        .collect())
+}
+
+/// marks the `paths` (if they are tracked) in the git index of `dir` working tree, 
+/// so `git status` doesn't re-check them after clearing
+/// 
+/// # return
+/// paths (relative to `dir`) that were marked
+pub fn mark_index<P: AsRef<Path>>(
+    dir: impl AsRef<Path>, 
+    paths: impl IntoIterator<Item = P>, 
+    mark: IndexMark,
+) -> std::io::Result<Vec<PathBuf>> {
+    let dir = dir.as_ref();
+    let tracked = tracked_paths(dir, paths)?;
+    if tracked.is_empty() { return Ok(tracked) }
+
+    let mark_arg = match mark {
+        IndexMark::AssumeUnchanged => "--assume-unchanged",
+        IndexMark::SkipWorktree => "--skip-worktree",
+    };
+    let mut child = Command::new("git")
+        .arg("-C").arg(dir)
+        .args(["update-index", "-z", mark_arg, "--stdin"])
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let mut stdin_paths = Vec::new();
+    for path in &tracked {
+        stdin_paths.extend_from_slice(path.as_os_str().as_encoded_bytes());
+        stdin_paths.push(b'\0');
+    }
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(&stdin_paths)?;
+    }
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(std::io::Error::other(format!("`git update-index {mark_arg}` failed: {}", stderr.trim())))
+    }
+    Ok(tracked)
+}
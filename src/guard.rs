@@ -0,0 +1,118 @@
+use std::path::{Path, PathBuf};
+
+/// why a path must not be cleared
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Protection {
+    /// a filesystem root (`/`, `C:\`)
+    FsRoot,
+    /// the home dir of the user or a dir that contains it (e.g. `/home`), but not its subdirs
+    Home,
+    /// a `.git` dir or anything inside of it
+    GitInternals,
+    /// a path outside of the declared root
+    OutsideRoot(PathBuf),
+    /// a path from the user protected list (or inside of it)
+    Listed(PathBuf),
+}
+
+impl std::fmt::Display for Protection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::FsRoot => write!(f, "filesystem root"),
+            Self::Home => write!(f, "home dir"),
+            Self::GitInternals => write!(f, "git internals"),
+            Self::OutsideRoot(root) => write!(f, "outside of the root {root:?}"),
+            Self::Listed(path) => write!(f, "protected path {path:?}"),
+        }
+    }
+}
+
+/// safeguard against clearing of dangerous targets:
+/// filesystem roots, the home dir (& dirs that contain it), `.git` internals,
+/// paths outside of the root (if set) & paths of the protected list
+///
+/// paths are checked after resolving symlinks, so a link can't lead out of the root
+#[derive(Debug, Clone)]
+pub struct PathGuard {
+    root: Option<PathBuf>,
+    home: Option<PathBuf>,
+    protected: Vec<PathBuf>,
+    allow_dangerous: bool,
+}
+
+impl Default for PathGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PathGuard {
+    /// guard with the built-in protections (home dir is taken from `HOME` / `USERPROFILE`)
+    pub fn new() -> Self {
+        let home = std::env::var_os("HOME")
+            .or_else(||std::env::var_os("USERPROFILE"))
+            .and_then(|home|std::fs::canonicalize(home).ok());
+        Self { root: None, home, protected: vec![], allow_dangerous: false }
+    }
+
+    /// the home dir that is protected (with its ancestors) instead of the one from the environment
+    pub fn set_home(&mut self, home: impl AsRef<Path>) -> std::io::Result<()> {
+        self.home = Some(std::fs::canonicalize(home)?);
+        Ok(())
+    }
+
+    /// only paths inside of the `root` can be cleared
+    pub fn set_root(&mut self, root: impl AsRef<Path>) -> std::io::Result<()> {
+        self.root = Some(std::fs::canonicalize(root)?);
+        Ok(())
+    }
+
+    /// disables the built-in protections (filesystem roots, the home dir, `.git` internals & the root),
+    /// the protected list stays in force
+    pub fn set_allow_dangerous(&mut self, allow_dangerous: bool) {
+        self.allow_dangerous = allow_dangerous
+    }
+
+    /// protects the `path` & everything inside of it
+    pub fn add_protected(&mut self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        self.protected.push(std::fs::canonicalize(path)?);
+        Ok(())
+    }
+
+    /// why the `path` must not be cleared
+    ///
+    /// # return
+    /// * `None` if the path is not protected or doesn't exist
+    pub fn protection(&self, path: impl AsRef<Path>) -> Option<Protection> {
+        let path = std::fs::canonicalize(path).ok()?;
+
+        if !self.allow_dangerous {
+            if path.parent().is_none() { return Some(Protection::FsRoot) }
+            // recursive clearing of `/home` would clear the home dir too
+            if self.home.as_ref().is_some_and(|home|home.starts_with(&path)) { return Some(Protection::Home) }
+            if path.components().any(|x|x.as_os_str() == ".git") { return Some(Protection::GitInternals) }
+            if let Some(root) = self.root.as_ref().filter(|root|!path.starts_with(root)) {
+                return Some(Protection::OutsideRoot(root.clone()))
+            }
+        }
+        self.protected.iter()
+            .find(|protected|path.starts_with(protected))
+            .map(|protected|Protection::Listed(protected.clone()))
+    }
+
+    pub fn is_allowed(&self, path: impl AsRef<Path>) -> bool {
+        self.protection(path).is_none()
+    }
+
+    /// same as `protection` but as an error of `PermissionDenied` kind
+    pub fn check(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let path = path.as_ref();
+        match self.protection(path) {
+            Some(protection) => Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                format!("{path:?} is protected: {protection}"),
+            )),
+            None => Ok(()),
+        }
+    }
+}
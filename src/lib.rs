@@ -2,7 +2,12 @@
 mod tests;
 
 pub mod clear_act;
-pub use clear_act::{ClearFile, ClearDir};
+pub use clear_act::{ClearFile, ClearDir, WrapD};
 pub use clear_act::{ConstChangeContF as ConstChgContF, ConstChangeContD as ConstChgContD};
 
 pub mod filter;
+pub mod path_list;
+pub mod git;
+pub mod guard;
+pub mod snapshot;
+pub mod config;
@@ -1,9 +1,52 @@
 use clear_file_preserve_meta as cl;
 use cl::{ClearFile, ClearDir};
-use clap::Parser;
+use clap::{ArgAction, ArgMatches, Args, CommandFactory, FromArgMatches, Parser, Subcommand};
+use clap::parser::ValueSource;
 
+/// clears files while preserving their metadata
+/// 
+/// without a subcommand works as `clear`
 #[derive(Debug, Parser)]
+#[command(args_conflicts_with_subcommands=true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    clear: ClearArgs,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// clear selected files while preserving metadata
+    Clear(ClearArgs),
+    /// list files that would be cleared (with their sizes) without changing anything
+    Preview(SelectArgs),
+    /// save size & times of selected files and their dirs to a snapshot
+    Snapshot {
+        #[command(flatten)]
+        select: SelectArgs,
+        /// snapshot file (`-` for stdout)
+        #[clap(short, long)]
+        output: std::path::PathBuf,
+    },
+    /// check that times of files & dirs match the snapshot
+    Verify {
+        /// snapshot file (`-` for stdin)
+        snapshot: std::path::PathBuf,
+        /// check sizes of files too
+        #[clap(long)]
+        size: bool,
+    },
+    /// restore times of files & dirs from the snapshot
+    Restore {
+        /// snapshot file (`-` for stdin)
+        snapshot: std::path::PathBuf,
+    },
+}
+
+/// which files are selected (shared by all subcommands that work with files)
+#[derive(Debug, Args)]
+struct SelectArgs {
     #[clap(short)]
     /// set of files that need to be clearead while preserving metadata
     file_clear: Vec<String>,
@@ -12,10 +55,70 @@ struct Cli {
     /// by default clearing is non-recursive
     /// for recursive dir cllearing add to beginning `+`
     /// for explicit non-recursive dir cllearing add to beginning `!` 
+    /// for a dir name starting with `+`, `!` or `\` add to beginning `\`
     dir_clear: Vec<String>,
-    /// new content for cleared files 
-    #[clap(short, long, default_value_t={"\n".into()})]
-    new_content: String,
+    /// dirs that need to be cleared (names are literal, without `+`/`!` prefixes)
+    /// 
+    /// a dir named as a subcommand (`clear`, `preview`, `snapshot`, `verify`, `restore`) 
+    /// is parsed as the subcommand: give it as `./clear`, after `--` (`-- clear`) or by `-d`
+    #[clap(value_name="DIR")]
+    dirs: Vec<std::path::PathBuf>,
+    /// clear dirs recursively
+    /// 
+    /// `-r`, `--no-recursive` & `--max-depth` apply to the dirs after them,
+    /// dirs before all of them use the last one (so given once they apply to all dirs)
+    #[clap(short, long, num_args=0, default_missing_value="true", action=ArgAction::Append)]
+    recursive: Vec<bool>,
+    /// clear only files of dirs themselves (the default)
+    #[clap(long, num_args=0, default_missing_value="true", action=ArgAction::Append)]
+    no_recursive: Vec<bool>,
+    /// clear dirs recursively down to the depth (0 - only files of dirs themselves)
+    #[clap(long, action=ArgAction::Append)]
+    max_depth: Vec<usize>,
+    /// dirs of `-d` & `DIR` with their max depth (`None` - no limit) in the command line order
+    #[clap(skip)]
+    dir_depths: Vec<(std::path::PathBuf, Option<usize>)>,
+    /// read paths of files to clear from the file (`-` for stdin), one per line
+    #[clap(long)]
+    files_from: Option<std::path::PathBuf>,
+    /// with `--files-from`: paths are delimited by NUL (e.g. `find -print0`, `git ls-files -z`)
+    #[clap(short='0', long, requires="files_from")]
+    null: bool,
+    /// what to do with files with multiple hard links in dirs:
+    /// `once` (clear the file once, under all links), `skip` or `break` (clear only the matched path)
+    #[clap(long, value_parser=parse_hard_link_policy, default_value="once")]
+    hard_links: cl::clear_act::HardLinkPolicy,
+    /// in dirs clear only files that are not ignored by `.gitignore`, `.ignore` & `.cfpmignore` files
+    #[clap(long)]
+    gitignore: bool,
+    /// in dirs clear only files that are ignored by `.gitignore`, `.ignore` & `.cfpmignore` files
+    #[clap(long, conflicts_with="gitignore")]
+    only_ignored: bool,
+    /// don't filter by ignore files (turns off `gitignore` & `only-ignored` of the profile)
+    #[clap(long, conflicts_with_all=["gitignore", "only_ignored"])]
+    no_gitignore: bool,
+    /// one more ignore file name for `--gitignore` / `--only-ignored`
+    #[clap(long)]
+    ignore_file: Vec<String>,
+    /// in dirs clear only files selected by local git: `tracked`, `untracked` or `modified` (differ from HEAD)
+    #[clap(long, value_parser=["tracked", "untracked", "modified"])]
+    git: Option<String>,
+    /// in dirs clear only files that differ from the git commit
+    #[clap(long, conflicts_with="git")]
+    git_since: Option<String>,
+    /// clear only paths inside of the dir
+    #[clap(long)]
+    root: Option<std::path::PathBuf>,
+    /// never clear the path (or anything inside of it); can be repeated
+    #[clap(long)]
+    protect: Vec<std::path::PathBuf>,
+    /// disable safeguards: allow clearing of filesystem roots, the home dir, `.git` internals
+    /// & paths outside of `--root` (`--protect` paths stay protected)
+    #[clap(long)]
+    allow_dangerous: bool,
+    /// keep safeguards (turns off `allow-dangerous` of the profile)
+    #[clap(long, conflicts_with="allow_dangerous")]
+    no_allow_dangerous: bool,
     /// white list regex for cleared files
     #[clap(short='w',long="wlr")]
     white_list_regex: Option<String>,
@@ -25,41 +128,920 @@ struct Cli {
     /// regex black list for cleared dir
     #[clap(long="dblr")]
     dir_black_list_regex: Option<String>,
+    /// take options that are not given in the command line from the named profile of the config
+    /// (`--no-*` flags turn off boolean options of the profile)
+    #[clap(long)]
+    profile: Option<String>,
+    /// config with profiles (by default: the nearest `.cfpm.toml` & `~/.config/cfpm/config.toml`)
+    #[clap(long, requires="profile")]
+    config: Option<std::path::PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct ClearArgs {
+    #[command(flatten)]
+    select: SelectArgs,
+    /// new content for cleared files 
+    #[clap(short, long, default_value_t={"\n".into()})]
+    new_content: String,
+    /// new content for cleared files is the raw bytes of the file
+    #[clap(long, conflicts_with_all=["new_content", "content_stdin"])]
+    content_file: Option<std::path::PathBuf>,
+    /// new content for cleared files is the raw bytes read from stdin
+    #[clap(long, conflicts_with="new_content")]
+    content_stdin: bool,
+    /// normalize the new content to the BOM & dominant line ending of each cleared file
+    /// (only the new content of `-n`, `--content-file` & `--content-stdin`)
+    #[clap(long, conflicts_with_all=["fill", "fill_zero", "punch_hole", "stub", "json_skeleton", "src_stub", "dummy", "keep_lines"])]
+    match_style: bool,
+    /// don't normalize the new content (turns off `match-style` of the profile)
+    #[clap(long, conflicts_with="match_style")]
+    no_match_style: bool,
+    /// overwrite cleared files by the repeated pattern while keeping their size
+    #[clap(long, conflicts_with_all=["new_content", "content_file", "content_stdin", "fill_zero"])]
+    fill: Option<String>,
+    /// overwrite cleared files by zeros while keeping their size
+    #[clap(long, conflicts_with_all=["new_content", "content_file", "content_stdin"])]
+    fill_zero: bool,
+    /// securely overwrite old content before clearing by the passes (comma separated):
+    /// `zero`, `random` or a byte value (`255`, `0xff`)
+    #[clap(long, value_delimiter=',', value_parser=parse_shred_pass, conflicts_with_all=["fill", "fill_zero"])]
+    shred: Vec<cl::clear_act::ShredPass>,
+    /// deallocate storage of cleared files (punch a hole) while keeping their size
+    #[clap(long, conflicts_with_all=["new_content", "content_file", "content_stdin", "fill", "fill_zero", "shred"])]
+    punch_hole: bool,
+    /// with `--punch-hole`: fail instead of zero-filling if the filesystem can't punch holes
+    #[clap(long, requires="punch_hole")]
+    punch_strict: bool,
+    /// change content of cleared files to the minimal valid document of their format
+    /// (JSON, TOML, YAML, XML, zip, tar, tar.gz, gzip), other files get the new content
+    #[clap(long, conflicts_with_all=["fill", "fill_zero", "shred", "punch_hole"])]
+    stub: bool,
+    /// change content of cleared JSON files to their skeleton:
+    /// same keys & nesting with zero leaf values and arrays of one element
+    #[clap(long, conflicts_with_all=["new_content", "content_file", "content_stdin", "fill", "fill_zero", "shred", "punch_hole", "stub"])]
+    json_skeleton: bool,
+    /// keep declarations of cleared Rust/C/C++ sources but empty function bodies,
+    /// other files get the new content
+    #[clap(long, conflicts_with_all=["fill", "fill_zero", "shred", "punch_hole", "stub", "json_skeleton"])]
+    src_stub: bool,
+    /// replace content of cleared files by dummy content of the same size:
+    /// `random` bytes or `lorem` ipsum text, deterministic by `--seed` & path
+    #[clap(long, value_parser=parse_dummy_kind, conflicts_with_all=["new_content", "content_file", "content_stdin", "fill", "fill_zero", "shred", "punch_hole", "stub", "json_skeleton", "src_stub"])]
+    dummy: Option<cl::clear_act::DummyKind>,
+    /// seed of the dummy content
+    #[clap(long, default_value_t=0, requires="dummy")]
+    seed: u64,
+    /// dummy content is seeded by paths relative to the dir 
+    /// (default: the cleared dir or the dir of a cleared file)
+    #[clap(long, requires="dummy")]
+    seed_root: Option<std::path::PathBuf>,
+    /// keep the number of lines (and their endings) of cleared files but blank their content
+    #[clap(long, conflicts_with_all=["new_content", "content_file", "content_stdin", "fill", "fill_zero", "shred", "punch_hole", "stub", "json_skeleton", "src_stub", "dummy"])]
+    keep_lines: bool,
+    /// with `--keep-lines`: mask each non-whitespace char by the char instead of blanking
+    #[clap(long, requires="keep_lines")]
+    line_mask: Option<char>,
+    /// with `--keep-lines`: convert all line endings to LF
+    #[clap(long, requires="keep_lines")]
+    lf: bool,
+    /// clear atomically: write the new content to a temp file with the same metadata
+    /// & rename it over the original (breaks hard links); 
+    /// multi-linked files in dirs are still cleared in place with `--hard-links once`
+    #[clap(long, conflicts_with="shred")]
+    atomic: bool,
+    /// clear in place (turns off `atomic` of the profile)
+    #[clap(long, conflicts_with="atomic")]
+    no_atomic: bool,
+    /// after clearing, mark cleared tracked files in the git index (`assume-unchanged` or `skip-worktree`)
+    /// so `git status` stays quiet, or just `report` them
+    #[clap(long, value_parser=["assume-unchanged", "skip-worktree", "report"])]
+    git_index: Option<String>,
+    /// ask before clearing each file: [y]es, [n]o, [a]ll (the rest without asking) or [q]uit
+    #[clap(short, long, conflicts_with="content_stdin")]
+    interactive: bool,
+    /// print the matched files with their total size & ask once before clearing
+    #[clap(long, conflicts_with="content_stdin")]
+    confirm: bool,
+}
+
+/// filters & safeguards built from `SelectArgs`
+struct Selection {
+    file_filter: cl::filter::FileFilter,
+    dir_filter: cl::filter::DirFilter,
+    ignore_filter: Option<cl::filter::IgnoreFilter>,
+    path_guard: Option<cl::guard::PathGuard>,
+}
+
+impl Selection {
+    fn new(select: &SelectArgs) -> std::io::Result<Self> {
+        let white_list = select.white_list_regex.as_deref();
+        let black_list = select.black_list_regex.as_deref();
+        let dir_black_list = select.dir_black_list_regex.as_deref();
+
+        Ok(Self {
+            file_filter: cl::filter::FileFilter::new(white_list, black_list)
+                .unwrap_or_else(|err|panic!("regex error: {err}")),
+            dir_filter: cl::filter::DirFilter::new(None, dir_black_list)
+                .unwrap_or_else(|err|panic!("regex error: {err}")),
+            ignore_filter: ignore_filter(select),
+            path_guard: path_guard(select)?,
+        })
+    }
+}
+
+impl SelectArgs {
+    /// the profile of `--profile` (empty if not set)
+    fn load_profile(&self) -> std::io::Result<cl::config::Profile> {
+        use cl::config::{find_profile, Config};
+        let Some(name) = &self.profile else { return Ok(Default::default()) };
+
+        let profile = match &self.config {
+            Some(path) => Config::read(path)?.profile.remove(name),
+            None => find_profile(Config::default_paths("."), name)?,
+        };
+        profile.ok_or_else(||std::io::Error::new(std::io::ErrorKind::NotFound, format!("profile `{name}` is not found")))
+    }
+
+    /// sets options that are not given in the command line from the profile
+    /// (dirs of the profile are set by `resolve_dirs`)
+    fn apply_profile(&mut self, profile: &cl::config::Profile, matches: &ArgMatches) -> Result<(), String> {
+        let is_set = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+        if !self.has_paths(matches) {
+            self.file_clear = profile.files.iter().map(|x|x.to_string_lossy().into_owned()).collect();
+        }
+        if !is_set("white_list_regex") && profile.white_list.is_some() {
+            self.white_list_regex = profile.white_list.clone();
+        }
+        if !is_set("black_list_regex") && profile.black_list.is_some() {
+            self.black_list_regex = profile.black_list.clone();
+        }
+        if !is_set("dir_black_list_regex") && profile.dir_black_list.is_some() {
+            self.dir_black_list_regex = profile.dir_black_list.clone();
+        }
+        if !is_set("gitignore") && !is_set("only_ignored") && !is_set("no_gitignore") {
+            self.gitignore = profile.gitignore;
+            self.only_ignored = profile.only_ignored;
+        }
+        if !is_set("ignore_file") && !profile.ignore_file.is_empty() {
+            self.ignore_file = profile.ignore_file.clone();
+        }
+        if !is_set("git") && !is_set("git_since") {
+            if let Some(git) = profile.git.as_deref() {
+                if !["tracked", "untracked", "modified"].contains(&git) {
+                    return Err(format!("profile: unknown git selection `{git}`"))
+                }
+            }
+            self.git = profile.git.clone();
+            self.git_since = profile.git_since.clone();
+        }
+        if let (false, Some(hard_links)) = (is_set("hard_links"), &profile.hard_links) {
+            self.hard_links = parse_hard_link_policy(hard_links)?;
+        }
+        if !is_set("root") && profile.root.is_some() {
+            self.root = profile.root.clone();
+        }
+        if !is_set("protect") && !profile.protect.is_empty() {
+            self.protect = profile.protect.clone();
+        }
+        if !is_set("allow_dangerous") && !is_set("no_allow_dangerous") {
+            self.allow_dangerous = profile.allow_dangerous;
+        }
+        Ok(())
+    }
+
+    /// are files or dirs to clear given in the command line?
+    fn has_paths(&self, matches: &ArgMatches) -> bool {
+        ["file_clear", "dir_clear", "dirs", "files_from"].iter()
+            .any(|&id|matches.value_source(id) == Some(ValueSource::CommandLine))
+    }
+
+    /// fills `dir_depths` by positions of dirs & recursion flags in the command line
+    /// 
+    /// without recursion flags dirs use the depth of the `profile`,
+    /// dirs of the `profile` are used only if no files or dirs are given in the command line
+    fn resolve_dirs(&mut self, matches: &ArgMatches, profile: &cl::config::Profile) {
+        let indices = |id: &str| matches.indices_of(id).into_iter().flatten();
+
+        // (index, max depth) of recursion flags
+        let mut depth_flags: Vec<(usize, Option<usize>)> = indices("recursive").map(|i|(i, None))
+            .chain(indices("no_recursive").map(|i|(i, Some(0))))
+            .chain(indices("max_depth").zip(&self.max_depth).map(|(i, &depth)|(i, Some(depth))))
+            .collect();
+        depth_flags.sort();
+        let last_depth = depth_flags.last().map(|&(_, depth)|depth).unwrap_or(profile.dir_depth());
+        let depth_at = |i: usize| depth_flags.iter().rev()
+            .find(|&&(flag_i, _)|flag_i < i)
+            .map(|&(_, depth)|depth)
+            .unwrap_or(last_depth);
+
+        let mut dirs: Vec<(usize, std::path::PathBuf, Option<usize>)> = vec![];
+        for (i, dir) in indices("dir_clear").zip(&self.dir_clear) {
+            // legacy prefixes take precedence over the flags
+            let (dir, depth) = match dir.chars().next() {
+                Some('+') => (&dir[1..], None),
+                Some('!') => (&dir[1..], Some(0)),
+                Some('\\') => (&dir[1..], depth_at(i)),
+                _ => (dir.as_str(), depth_at(i)),
+            };
+            dirs.push((i, dir.into(), depth));
+        }
+        for (i, dir) in indices("dirs").zip(&self.dirs) {
+            dirs.push((i, dir.clone(), depth_at(i)));
+        }
+        dirs.sort_by_key(|&(i, _, _)|i);
+        self.dir_depths = dirs.into_iter().map(|(_, dir, depth)|(dir, depth)).collect();
+
+        if !self.has_paths(matches) {
+            self.dir_depths.extend(profile.dirs.iter().map(|dir|(dir.clone(), last_depth)));
+        }
+    }
+
+    /// applies the profile & resolves dirs
+    fn prepare(&mut self, matches: &ArgMatches) -> std::io::Result<cl::config::Profile> {
+        let profile = self.load_profile()?;
+        self.apply_profile(&profile, matches)
+            .unwrap_or_else(|err|Cli::command().error(clap::error::ErrorKind::InvalidValue, err).exit());
+        self.resolve_dirs(matches, &profile);
+        Ok(profile)
+    }
+}
+
+impl ClearArgs {
+    /// sets options that are not given in the command line from the profile;
+    /// content options of the profile are used only if none of them is given in the command line
+    fn apply_profile(&mut self, profile: &cl::config::Profile, matches: &ArgMatches) -> Result<(), String> {
+        let is_set = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+        let content_ids = [
+            "new_content", "content_file", "content_stdin", "fill", "fill_zero", "shred", "punch_hole", 
+            "stub", "json_skeleton", "src_stub", "dummy", "keep_lines",
+        ];
+        let profile_content = !content_ids.iter().any(|id|is_set(id));
+        if profile_content {
+            if let Some(new_content) = &profile.new_content {
+                self.new_content = new_content.clone();
+            }
+            self.content_file = profile.content_file.clone();
+            self.fill = profile.fill.clone();
+            self.fill_zero = profile.fill_zero;
+            self.shred = profile.shred.iter().map(|pass|parse_shred_pass(pass)).collect::<Result<_, _>>()?;
+            self.punch_hole = profile.punch_hole;
+            self.stub = profile.stub;
+            self.json_skeleton = profile.json_skeleton;
+            self.src_stub = profile.src_stub;
+            self.dummy = profile.dummy.as_deref().map(parse_dummy_kind).transpose()?;
+            self.keep_lines = profile.keep_lines;
+        }
+        if let (false, Some(seed)) = (is_set("seed"), profile.seed) {
+            self.seed = seed;
+        }
+        if !is_set("match_style") && !is_set("no_match_style") {
+            self.match_style = profile.match_style;
+        }
+        if !is_set("atomic") && !is_set("no_atomic") {
+            self.atomic = profile.atomic;
+        }
+        if let (false, Some(git_index)) = (is_set("git_index"), profile.git_index.as_deref()) {
+            if !["assume-unchanged", "skip-worktree", "report"].contains(&git_index) {
+                return Err(format!("profile: unknown git index mark `{git_index}`"))
+            }
+            self.git_index = profile.git_index.clone();
+        }
+
+        let new_content = is_set("new_content") || (profile_content && profile.new_content.is_some());
+        self.check_conflicts(new_content)
+    }
+
+    /// checks options taken from the profile by the same conflict rules as in the command line
+    fn check_conflicts(&self, new_content: bool) -> Result<(), String> {
+        let used = [
+            ("new_content", new_content),
+            ("content_file", self.content_file.is_some()),
+            ("content_stdin", self.content_stdin),
+            ("match_style", self.match_style),
+            ("fill", self.fill.is_some()),
+            ("fill_zero", self.fill_zero),
+            ("shred", !self.shred.is_empty()),
+            ("punch_hole", self.punch_hole),
+            ("stub", self.stub),
+            ("json_skeleton", self.json_skeleton),
+            ("src_stub", self.src_stub),
+            ("dummy", self.dummy.is_some()),
+            ("keep_lines", self.keep_lines),
+            ("atomic", self.atomic),
+        ];
+        let used: Vec<&str> = used.iter().filter(|(_, used)|*used).map(|(id, _)|*id).collect();
+
+        let cmd = Self::augment_args(clap::Command::new("profile"));
+        let long = |arg: &clap::Arg| arg.get_long().unwrap_or(arg.get_id().as_str()).to_owned();
+        for arg in cmd.get_arguments().filter(|arg|used.contains(&arg.get_id().as_str())) {
+            let conflict = cmd.get_arg_conflicts_with(arg).into_iter()
+                .find(|other|used.contains(&other.get_id().as_str()));
+            if let Some(other) = conflict {
+                return Err(format!("profile: `{}` can't be used with `{}`", long(arg), long(other)))
+            }
+        }
+        Ok(())
+    }
+
+    fn prepare(&mut self, matches: &ArgMatches) -> std::io::Result<()> {
+        let profile = self.select.prepare(matches)?;
+        self.apply_profile(&profile, matches)
+            .unwrap_or_else(|err|Cli::command().error(clap::error::ErrorKind::InvalidValue, err).exit());
+        Ok(())
+    }
 }
 
 
 fn main() -> std::io::Result<()> {
-    let cli = Cli::parse();
-    
-    let white_list = cli.white_list_regex.as_ref().map(|re|re.as_str());
-    let black_list = cli.black_list_regex.as_ref().map(|re|re.as_str());
-    let dir_black_list = cli.dir_black_list_regex.as_ref().map(|re|re.as_str());
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err|err.exit());
+    match (&mut cli.command, matches.subcommand()) {
+        (None, _) => cli.clear.prepare(&matches)?,
+        (Some(Command::Clear(args)), Some((_, matches))) => args.prepare(matches)?,
+        (Some(Command::Preview(select)), Some((_, matches))) |
+        (Some(Command::Snapshot { select, .. }), Some((_, matches))) => { select.prepare(matches)?; }
+        _ => {}
+    }
+
+    match &cli.command {
+        None => clear(&cli.clear),
+        Some(Command::Clear(args)) => clear(args),
+        Some(Command::Preview(select)) => preview(select),
+        Some(Command::Snapshot { select, output }) => snapshot(select, output),
+        Some(Command::Verify { snapshot, size }) => verify(snapshot, *size),
+        Some(Command::Restore { snapshot }) => restore(snapshot),
+    }
+}
+
+fn clear(args: &ClearArgs) -> std::io::Result<()> {
+    let stdin_users = [
+        (args.content_stdin, "--content-stdin"),
+        (args.select.files_from.as_deref() == Some("-".as_ref()), "--files-from -"),
+        (args.interactive || args.confirm, "--interactive / --confirm"),
+    ];
+    if let [(_, first), (_, second), ..] = stdin_users.iter().filter(|(used, _)|*used).collect::<Vec<_>>()[..] {
+        Cli::command().error(clap::error::ErrorKind::ArgumentConflict, 
+            format!("`{first}` & `{second}` can't both read stdin")).exit()
+    }
+    // shred passes would overwrite the temp file of the atomic clearing
+    if !args.shred.is_empty() && args.select.hard_links == cl::clear_act::HardLinkPolicy::BreakLink {
+        Cli::command().error(clap::error::ErrorKind::ArgumentConflict, 
+            "`--shred` can't be used with `--hard-links break`").exit()
+    }
+
+    // exactly the confirmed files are cleared (new files of the dirs are not)
+    let confirmed = if args.confirm {
+        let selected = select_files(&args.select)?;
+        print_selected(&selected.report)?;
+        if selected.report.cleared.is_empty() { return Ok(()) }
+        if !matches!(ask("clear them? [y/N]: ").as_deref(), Some("y" | "yes")) {
+            println!("nothing is cleared");
+            return Ok(())
+        }
+        Some(selected)
+    } else {
+        None
+    };
+
+    let selection = Selection::new(&args.select)?;
+    let file_filter = &selection.file_filter;
+    let dir_filter = &selection.dir_filter;
+
+    if args.punch_hole {
+        let mut file_act = cl::clear_act::PunchHoleF::new(file_filter);
+        if args.punch_strict {
+            file_act.set_fallback(cl::clear_act::PunchFallback::Error);
+        }
+        let mut fd_changer = cl::WrapD::wrap(file_act, dir_filter);
+        clear_all(&mut fd_changer, args, &selection, confirmed.as_ref());
+        println!("freed {} bytes", fd_changer.file_act().freed_bytes());
+    } else if args.stub {
+        let file_act = cl::clear_act::StubF::new(new_content(args)?, file_filter);
+        clear_all(&mut cl::WrapD::wrap(file_act, dir_filter), args, &selection, confirmed.as_ref());
+    } else if args.json_skeleton {
+        let file_act = cl::clear_act::JsonSkeletonF::new(file_filter);
+        clear_all(&mut cl::WrapD::wrap(file_act, dir_filter), args, &selection, confirmed.as_ref());
+    } else if args.src_stub {
+        let file_act = cl::clear_act::SrcStubF::new(new_content(args)?, file_filter);
+        clear_all(&mut cl::WrapD::wrap(file_act, dir_filter), args, &selection, confirmed.as_ref());
+    } else if let Some(kind) = args.dummy {
+        let mut file_act = cl::clear_act::DummyF::new(kind, args.seed, file_filter);
+        file_act.set_root(args.seed_root.clone())?;
+        clear_all(&mut cl::WrapD::wrap(file_act, dir_filter), args, &selection, confirmed.as_ref());
+    } else if args.keep_lines {
+        let mut file_act = cl::clear_act::KeepLinesF::new(file_filter);
+        if let Some(mask) = args.line_mask {
+            let mask = u8::try_from(mask).ok().filter(u8::is_ascii)
+                .unwrap_or_else(||panic!("line mask must be an ASCII char: {mask:?}"));
+            file_act.set_mask(cl::clear_act::LineMask::Mask(mask));
+        }
+        file_act.set_keep_endings(!args.lf);
+        clear_all(&mut cl::WrapD::wrap(file_act, dir_filter), args, &selection, confirmed.as_ref());
+    } else if args.fill_zero {
+        let file_act = cl::clear_act::SameSizeFillF::new_zero(file_filter);
+        clear_all(&mut cl::WrapD::wrap(file_act, dir_filter), args, &selection, confirmed.as_ref());
+    } else if let Some(pattern) = &args.fill {
+        let file_act = cl::clear_act::SameSizeFillF::new(pattern, file_filter);
+        clear_all(&mut cl::WrapD::wrap(file_act, dir_filter), args, &selection, confirmed.as_ref());
+    } else {
+        let new_content = new_content(args)?;
+        let mut fd_cont_changer = cl::ConstChgContD::new(new_content, file_filter, dir_filter);
+        fd_cont_changer.file_act_mut().set_shred_passes(args.shred.clone());
+        fd_cont_changer.file_act_mut().set_match_style(args.match_style);
+        clear_all(&mut fd_cont_changer, args, &selection, confirmed.as_ref());
+    }
+
+    Ok(())
+}
+
+fn preview(select: &SelectArgs) -> std::io::Result<()> {
+    print_selected(&select_files(select)?.report)
+}
+
+/// prints files of the dry run with their sizes
+fn print_selected(report: &cl::clear_act::ClearReport) -> std::io::Result<()> {
+    print_hard_links(report, false);
+
+    let mut total = 0;
+    for path in &report.cleared {
+        let size = std::fs::metadata(path)?.len();
+        total += size;
+        println!("{path:?} ({size} bytes)");
+    }
+    println!("{} files, {total} bytes", report.cleared.len());
+    Ok(())
+}
+
+fn snapshot(select: &SelectArgs, output: &std::path::Path) -> std::io::Result<()> {
+    let report = select_files(select)?.report;
+    let dirs = report.cleared.iter()
+        .filter_map(|path|path.parent())
+        .filter(|dir|!dir.as_os_str().is_empty());
+    let snapshot = cl::snapshot::Snapshot::capture(report.cleared.iter().map(|x|x.as_path()).chain(dirs))?;
+
+    if output.as_os_str() == "-" {
+        snapshot.write_to(std::io::stdout().lock())
+    } else {
+        snapshot.write_to(std::io::BufWriter::new(std::fs::File::create(output)?))
+    }
+}
+
+fn read_snapshot(path: &std::path::Path) -> std::io::Result<cl::snapshot::Snapshot> {
+    if path.as_os_str() == "-" {
+        cl::snapshot::Snapshot::read_from(std::io::stdin().lock())
+    } else {
+        cl::snapshot::Snapshot::read_from(std::io::BufReader::new(std::fs::File::open(path)?))
+    }
+}
+
+fn verify(snapshot: &std::path::Path, check_size: bool) -> std::io::Result<()> {
+    let mismatches = read_snapshot(snapshot)?.verify(check_size);
+    for mismatch in &mismatches {
+        println!("{mismatch}");
+    }
+    if !mismatches.is_empty() {
+        std::process::exit(1)
+    }
+    Ok(())
+}
+
+fn restore(snapshot: &std::path::Path) -> std::io::Result<()> {
+    let restored = read_snapshot(snapshot)?.restore_times()?;
+    println!("restored times of {restored} files & dirs");
+    Ok(())
+}
+
+fn new_content(args: &ClearArgs) -> std::io::Result<Vec<u8>> {
+    if let Some(path) = &args.content_file {
+        std::fs::read(path)
+    } else if args.content_stdin {
+        let mut content = Vec::new();
+        std::io::Read::read_to_end(&mut std::io::stdin(), &mut content)?;
+        Ok(content)
+    } else {
+        Ok(args.new_content.clone().into_bytes())
+    }
+}
+
+fn parse_shred_pass(pass: &str) -> Result<cl::clear_act::ShredPass, String> {
+    use cl::clear_act::ShredPass;
+    match pass {
+        "zero" => Ok(ShredPass::Zero),
+        "random" => Ok(ShredPass::Random),
+        _ => {
+            let byte = match pass.strip_prefix("0x") {
+                Some(hex) => u8::from_str_radix(hex, 16),
+                None => pass.parse(),
+            };
+            byte.map(ShredPass::Byte).map_err(|_|format!("unknown shred pass `{pass}`"))
+        }
+    }
+}
+
+fn parse_dummy_kind(kind: &str) -> Result<cl::clear_act::DummyKind, String> {
+    use cl::clear_act::DummyKind;
+    match kind {
+        "random" => Ok(DummyKind::Random),
+        "lorem" => Ok(DummyKind::Lorem),
+        _ => Err(format!("unknown dummy content `{kind}` (expected `random` or `lorem`)")),
+    }
+}
+
+fn parse_hard_link_policy(policy: &str) -> Result<cl::clear_act::HardLinkPolicy, String> {
+    use cl::clear_act::HardLinkPolicy;
+    match policy {
+        "once" => Ok(HardLinkPolicy::ClearOnce),
+        "skip" => Ok(HardLinkPolicy::Skip),
+        "break" => Ok(HardLinkPolicy::BreakLink),
+        _ => Err(format!("unknown hard links policy `{policy}` (expected `once`, `skip` or `break`)")),
+    }
+}
+
+/// `in_place`: cleared groups are reported as cleared in place (not atomically)
+fn print_hard_links(report: &cl::clear_act::ClearReport, in_place: bool) {
+    for group in &report.hard_links {
+        let state = match (group.cleared, in_place) {
+            (true, false) => "cleared",
+            (true, true) => "cleared in place, not atomically",
+            (false, _) => "not cleared",
+        };
+        println!("file with {} hard links ({state}): {:?}", group.nlink, group.paths);
+    }
+}
+
+fn print_invalid(report: &cl::clear_act::ClearReport) {
+    for path in &report.invalid {
+        println!("cant clear file {path:?}: its content doesn't fit the action")
+    }
+}
+
+fn git_select(select: &SelectArgs) -> Option<cl::git::GitSelect> {
+    use cl::git::GitSelect;
+    if let Some(commit) = &select.git_since {
+        return Some(GitSelect::ChangedSince(commit.clone()))
+    }
+    Some(match select.git.as_deref()? {
+        "tracked" => GitSelect::Tracked,
+        "untracked" => GitSelect::Untracked,
+        _ => GitSelect::Modified,
+    })
+}
+
+fn update_git_index(args: &ClearArgs, dir: &std::path::Path, cleared: &[std::path::PathBuf]) {
+    use cl::git::IndexMark;
+    let Some(git_index) = args.git_index.as_deref() else { return };
+    if cleared.is_empty() { return }
+
+    let mark = match git_index {
+        "assume-unchanged" => Some(IndexMark::AssumeUnchanged),
+        "skip-worktree" => Some(IndexMark::SkipWorktree),
+        _ => None,
+    };
+    let paths = match mark {
+        Some(mark) => cl::git::mark_index(dir, cleared, mark),
+        None => cl::git::tracked_paths(dir, cleared),
+    };
+    match paths {
+        Ok(paths) => for path in paths {
+            match mark {
+                Some(_) => println!("marked {git_index} in git index: {:?}", dir.join(path)),
+                None => println!("cleared tracked file: {:?}", dir.join(path)),
+            }
+        },
+        Err(err) => println!("cant update git index of {dir:?}: {err}"),
+    }
+}
+
+fn path_guard(select: &SelectArgs) -> std::io::Result<Option<cl::guard::PathGuard>> {
+    if select.allow_dangerous && select.protect.is_empty() { return Ok(None) }
 
-    let file_filter = cl::filter::FileFilter::new(white_list, black_list)
-        .unwrap_or_else(|err|panic!("regex error: {err}"));
-    let dir_filter = cl::filter::DirFilter::new(None, dir_black_list)
-        .unwrap_or_else(|err|panic!("regex error: {err}"));
+    let mut guard = cl::guard::PathGuard::new();
+    guard.set_allow_dangerous(select.allow_dangerous);
+    if let Some(root) = &select.root {
+        guard.set_root(root)?;
+    }
+    for path in &select.protect {
+        guard.add_protected(path)?;
+    }
+    Ok(Some(guard))
+}
+
+fn files_from(select: &SelectArgs) -> std::io::Result<Vec<std::path::PathBuf>> {
+    let Some(path) = &select.files_from else { return Ok(vec![]) };
+    let delim = if select.null { b'\0' } else { b'\n' };
+    if path.as_os_str() == "-" {
+        cl::path_list::read_paths(std::io::stdin().lock(), delim)
+    } else {
+        cl::path_list::read_paths(std::fs::File::open(path)?, delim)
+    }
+}
 
-    let mut fd_cont_changer = cl::ConstChgContD::new(&cli.new_content, &file_filter, &dir_filter);
+fn ignore_filter(select: &SelectArgs) -> Option<cl::filter::IgnoreFilter> {
+    use cl::filter::{IgnoreFilter, IgnoreMode};
 
+    let mode = if select.gitignore {
+        IgnoreMode::Unignored
+    } else if select.only_ignored {
+        IgnoreMode::Ignored
+    } else {
+        return None
+    };
 
-    for file_path in &cli.file_clear {
-        if let Err(err) = fd_cont_changer.clear_file(file_path) {
-            println!("cant clear file {file_path:?}: {err}")
+    let mut filter = IgnoreFilter::new(mode);
+    for name in &select.ignore_file {
+        filter.add_file_name(name);
+    }
+    Some(filter)
+}
+
+fn clear_all<'filter, F: ClearFile>(
+    fd_changer: &mut cl::WrapD<'filter, F>, 
+    args: &ClearArgs, 
+    selection: &'filter Selection,
+    confirmed: Option<&Selected>,
+) {
+    if args.atomic {
+        fd_changer.set_clear_mode(cl::clear_act::ClearMode::Atomic);
+    }
+    // the status line would mix with prompts
+    let progress = if args.interactive {
+        fd_changer.set_file_confirm(Some(Box::new(file_prompt())));
+        None
+    } else {
+        progress_status()
+    };
+    let has_progress = progress.is_some();
+    fd_changer.set_progress(progress);
+
+    // the git index is updated once per working tree (not per file)
+    let mut cleared_by_tree = std::collections::BTreeMap::<std::path::PathBuf, Vec<_>>::new();
+    clear_selected(fd_changer, &args.select, selection, confirmed, |source, mut report| {
+        if has_progress { clear_status() }
+        let dir = source.dir();
+        print_hard_links(&report, args.atomic && args.select.hard_links == cl::clear_act::HardLinkPolicy::ClearOnce);
+        print_invalid(&report);
+        if args.git_index.is_some() && !report.cleared.is_empty() {
+            let tree = cl::git::work_tree_root(dir).unwrap_or_else(||dir.to_path_buf());
+            cleared_by_tree.entry(tree).or_default().append(&mut report.cleared);
+        }
+    });
+    for (tree, cleared) in &cleared_by_tree {
+        update_git_index(args, tree, cleared);
+    }
+}
+
+/// per-file prompt of `--interactive`
+fn file_prompt() -> impl FnMut(&std::path::Path) -> bool {
+    let mut all = false;
+    let mut quit = false;
+    move |path| {
+        if all { return true }
+        if quit { return false }
+        let size = std::fs::metadata(path).map(|md|md.len()).unwrap_or(0);
+        loop {
+            match ask(&format!("clear {path:?} ({size} bytes)? [y]es/[n]o/[a]ll/[q]uit: ")).as_deref() {
+                Some("y" | "yes") => return true,
+                Some("n" | "no") => return false,
+                Some("a" | "all") => {
+                    all = true;
+                    return true
+                }
+                // the rest of files are skipped
+                Some("q" | "quit") | None => {
+                    quit = true;
+                    return false
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// status line of long runs on stderr (`None` if stderr is not a terminal)
+fn progress_status() -> Option<cl::clear_act::ProgressFn<'static>> {
+    use std::io::IsTerminal;
+    if !std::io::stderr().is_terminal() { return None }
+
+    let period = std::time::Duration::from_millis(100);
+    let mut last = std::time::Instant::now();
+    Some(Box::new(move |progress, path| {
+        if last.elapsed() < period { return }
+        last = std::time::Instant::now();
+        eprint!(
+            "\r\x1b[Kvisited {} files, cleared {}, freed {} bytes: {}", 
+            progress.visited, progress.cleared, progress.freed_bytes, path.display(),
+        );
+    }))
+}
+
+/// erases the status line of `progress_status`
+fn clear_status() {
+    use std::io::IsTerminal;
+    if std::io::stderr().is_terminal() {
+        eprint!("\r\x1b[K");
+    }
+}
+
+/// prints an error of clearing on a new line (not after the status line)
+fn print_clear_error(msg: std::fmt::Arguments) {
+    clear_status();
+    println!("{msg}");
+}
+
+/// asks on stderr & reads the answer (trimmed, lowercase) from stdin, `None` on EOF
+fn ask(question: &str) -> Option<String> {
+    eprint!("{question}");
+    let mut answer = String::new();
+    match std::io::stdin().read_line(&mut answer) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(answer.trim().to_lowercase()),
+    }
+}
+
+/// where files of a report come from
+enum Source<'a> {
+    /// a file of `-f` or `--files-from`
+    File(&'a std::path::Path),
+    /// a dir of `-d` or `DIR`
+    Dir(&'a std::path::Path),
+}
+
+impl Source<'_> {
+    /// the dir of the file or the dir itself
+    fn dir(&self) -> &std::path::Path {
+        match self {
+            Self::File(path) => path.parent().filter(|dir|!dir.as_os_str().is_empty()).unwrap_or(".".as_ref()),
+            Self::Dir(dir) => dir,
+        }
+    }
+}
+
+/// files of the dry run by their sources
+#[derive(Default)]
+struct Selected {
+    files: Vec<std::path::PathBuf>,
+    dirs: Vec<(std::path::PathBuf, Vec<std::path::PathBuf>)>,
+    /// all the files
+    report: cl::clear_act::ClearReport,
+}
+
+/// matched files in the dry run (see `ClearFile::is_dry_run`)
+fn select_files(select: &SelectArgs) -> std::io::Result<Selected> {
+    let selection = Selection::new(select)?;
+    let mut fd_changer = cl::ConstChgContD::new("", &selection.file_filter, &selection.dir_filter);
+    fd_changer.set_dry_run(true);
+    let progress = progress_status();
+    let has_progress = progress.is_some();
+    fd_changer.set_progress(progress);
+
+    let mut selected = Selected::default();
+    clear_selected(&mut fd_changer, select, &selection, None, |source, report| {
+        if has_progress { clear_status() }
+        match source {
+            Source::File(path) => selected.files.push(path.to_path_buf()),
+            Source::Dir(dir) => selected.dirs.push((dir.to_path_buf(), report.cleared.clone())),
+        }
+        selected.report.append(report)
+    });
+    Ok(selected)
+}
+
+/// clears the selected files & dirs (or only the `confirmed` files of them)
+/// 
+/// `on_report` gets the report of each file or dir with its source
+fn clear_selected<'filter, F: ClearFile>(
+    fd_changer: &mut cl::WrapD<'filter, F>, 
+    select: &SelectArgs, 
+    selection: &'filter Selection,
+    confirmed: Option<&Selected>,
+    mut on_report: impl FnMut(Source, cl::clear_act::ClearReport),
+) {
+    fd_changer.set_ignore_filter(selection.ignore_filter.as_ref());
+    fd_changer.set_path_guard(selection.path_guard.as_ref());
+    fd_changer.set_hard_link_policy(select.hard_links);
+
+    if let Some(confirmed) = confirmed {
+        for file_path in &confirmed.files {
+            match fd_changer.try_clear_file(file_path) {
+                Ok(true) => {
+                    let report = cl::clear_act::ClearReport { cleared: vec![file_path.clone()], ..Default::default() };
+                    on_report(Source::File(file_path), report);
+                }
+                Ok(false) => {}
+                Err(err) => print_clear_error(format_args!("cant clear file {file_path:?}: {err}")),
+            }
+        }
+        for (dir_path, paths) in &confirmed.dirs {
+            match fd_changer.clear_paths_report(dir_path, paths) {
+                Ok(report) => on_report(Source::Dir(dir_path), report),
+                Err(err) => print_clear_error(format_args!("cant (completely) clear dir {dir_path:?}: {err}")),
+            }
+        }
+        return
+    }
+
+    let check_guard = |path: &std::path::Path| match selection.path_guard.as_ref().map(|guard|guard.check(path)) {
+        Some(Err(err)) => {
+            print_clear_error(format_args!("refuse to clear {err} (use --allow-dangerous to override)"));
+            false
+        }
+        _ => true,
+    };
+
+    let files_from = files_from(select).unwrap_or_else(|err| {
+        print_clear_error(format_args!("cant read paths of --files-from: {err}"));
+        vec![]
+    });
+    let file_paths = select.file_clear.iter().map(std::path::Path::new)
+        .chain(files_from.iter().map(|path|path.as_path()));
+
+    for file_path in file_paths {
+        if !check_guard(file_path) { continue }
+        match fd_changer.try_clear_file(file_path) {
+            Ok(true) => {
+                let report = cl::clear_act::ClearReport { cleared: vec![file_path.to_path_buf()], ..Default::default() };
+                on_report(Source::File(file_path), report);
+            }
+            Ok(false) => {}
+            Err(err) => print_clear_error(format_args!("cant clear file {file_path:?}: {err}")),
         }
     }
     
-    for dir_path in &cli.dir_clear {
-        // non_recursive for files with first char '+'
-        let non_recursive = dir_path.starts_with("!");
-        let recursive = !non_recursive && dir_path.starts_with("+");
-        let dir_path = if recursive || non_recursive { &dir_path[1..] } else { dir_path };
+    for (dir_path, max_depth) in &select.dir_depths {
+        let dir_path = dir_path.as_path();
+        if !check_guard(dir_path) { continue }
         
-        fd_cont_changer.set_recursive(recursive);
-        if let Err(err) = fd_cont_changer.clear_dir_files(dir_path) {
-            println!("cant (completely) clear dir {dir_path:?}: {err}")
+        fd_changer.set_recursive(*max_depth != Some(0));
+        fd_changer.set_max_depth(*max_depth);
+        let report = match git_select(select) {
+            Some(git_select) => cl::git::git_paths(dir_path, &git_select).and_then(|paths| {
+                // git lists files recursively
+                let paths = paths.into_iter().filter(|path| {
+                    let depth = path.strip_prefix(dir_path).map(|x|x.components().count()).unwrap_or(1);
+                    max_depth.is_none_or(|max|depth <= max + 1)
+                });
+                fd_changer.clear_paths_report(dir_path, paths)
+            }),
+            None => fd_changer.clear_dir_files_report(dir_path),
+        };
+        match report {
+            Ok(report) => on_report(Source::Dir(dir_path), report),
+            Err(err) => print_clear_error(format_args!("cant (completely) clear dir {dir_path:?}: {err}")),
         }
     }
+}
 
-    Ok(())
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_new_content_bytes() {
+        let dir = "./tests/main_new_content";
+        std::fs::create_dir_all(dir).unwrap();
+        let path = format!("{dir}/content.bin");
+        let content = b"\xff\xfe stub\r\n\x00".to_vec();
+        std::fs::write(&path, &content).unwrap();
+
+        let cli = Cli::try_parse_from(["cfpm", "--content-file", &path]).unwrap();
+        assert_eq!(new_content(&cli.clear).unwrap(), content);
+
+        let cli = Cli::try_parse_from(["cfpm", "-n", "stub"]).unwrap();
+        assert_eq!(new_content(&cli.clear).unwrap(), b"stub");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_path_guard_allow_dangerous() {
+        let dir = "./tests/main_path_guard";
+        std::fs::create_dir_all(format!("{dir}/keep")).unwrap();
+
+        let cli = Cli::try_parse_from(["cfpm", "--allow-dangerous", "--protect", &format!("{dir}/keep"), dir]).unwrap();
+        let guard = path_guard(&cli.clear.select).unwrap().unwrap();
+        assert!(guard.check(format!("{dir}/keep")).is_err());
+        assert!(guard.check("/").is_ok());
+
+        let cli = Cli::try_parse_from(["cfpm", "--allow-dangerous", dir]).unwrap();
+        assert!(path_guard(&cli.clear.select).unwrap().is_none());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_profile_git_index() {
+        let matches = Cli::command().get_matches_from(["cfpm"]);
+        let mut cli = Cli::from_arg_matches(&matches).unwrap();
+
+        let mut profile = cl::config::Profile { git_index: Some("skip-worktree".into()), ..Default::default() };
+        cli.clear.apply_profile(&profile, &matches).unwrap();
+        assert_eq!(cli.clear.git_index.as_deref(), Some("skip-worktree"));
+
+        profile.git_index = Some("unknown".into());
+        assert!(cli.clear.apply_profile(&profile, &matches).is_err());
+    }
 }
@@ -0,0 +1,66 @@
+use std::io::Read;
+use std::path::PathBuf;
+
+/// splits `bytes` into paths by `delim` (usually `b'\n'` or `b'\0'`), empty items are skipped
+/// 
+/// on non-unix platforms paths must be UTF-8 (non UTF-8 paths are converted lossy)
+pub fn split_paths(bytes: &[u8], delim: u8) -> Vec<PathBuf> {
+    bytes.split(|&b|b == delim)
+        .filter(|path|!path.is_empty())
+        .map(path_from_bytes)
+        .collect()
+}
+
+/// reads a list of paths (e.g. from `find -print0` or `git ls-files -z`) delimited by `delim`
+/// 
+/// for `b'\n'` a trailing `\r` of each line is removed too (so CRLF lists work)
+pub fn read_paths(mut reader: impl Read, delim: u8) -> std::io::Result<Vec<PathBuf>> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    if delim != b'\n' { return Ok(split_paths(&bytes, delim)) }
+
+    Ok(bytes.split(|&b|b == delim)
+        .map(|line|line.strip_suffix(b"\r").unwrap_or(line))
+        .filter(|path|!path.is_empty())
+        .map(path_from_bytes)
+        .collect())
+}
+
+#[cfg(unix)]
+pub(crate) fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+}
+
+#[cfg(not(unix))]
+pub(crate) fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+}
+
+#[cfg(unix)]
+pub(crate) fn path_to_bytes(path: &std::path::Path) -> std::borrow::Cow<'_, [u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    std::borrow::Cow::Borrowed(path.as_os_str().as_bytes())
+}
+
+#[cfg(not(unix))]
+pub(crate) fn path_to_bytes(path: &std::path::Path) -> std::borrow::Cow<'_, [u8]> {
+    match path.to_string_lossy() {
+        std::borrow::Cow::Borrowed(path) => std::borrow::Cow::Borrowed(path.as_bytes()),
+        std::borrow::Cow::Owned(path) => std::borrow::Cow::Owned(path.into_bytes()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+    use super::read_paths;
+
+    #[test]
+    fn test_read_paths() -> std::io::Result<()> {
+        let paths = |list: &[&str]| list.iter().map(PathBuf::from).collect::<Vec<_>>();
+        assert_eq!(read_paths(&b"a.txt\r\n\nb c\n"[..], b'\n')?, paths(&["a.txt", "b c"]));
+        assert_eq!(read_paths(&b"a\nb\0c\r\0"[..], b'\0')?, paths(&["a\nb", "c\r"]));
+        Ok(())
+    }
+}
@@ -0,0 +1,234 @@
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use filetime::FileTime;
+
+use crate::path_list::{path_from_bytes, path_to_bytes};
+
+const HEADER: &str = "# clear_file_preserve_meta snapshot v1";
+
+/// metadata of a file or a dir that is preserved by clearing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotEntry {
+    pub path: PathBuf,
+    pub is_dir: bool,
+    pub size: u64,
+    pub mtime: FileTime,
+    pub atime: FileTime,
+}
+
+impl SnapshotEntry {
+    /// metadata of the `path` (it is not read, so its atime is not changed)
+    pub fn capture(path: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let path = path.into();
+        let md = std::fs::metadata(&path)?;
+        Ok(Self {
+            is_dir: md.is_dir(),
+            size: md.len(),
+            mtime: FileTime::from_last_modification_time(&md),
+            atime: FileTime::from_last_access_time(&md),
+            path,
+        })
+    }
+}
+
+/// difference between a snapshot entry & the current state of its path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mismatch {
+    Missing(PathBuf),
+    Size { path: PathBuf, expected: u64, actual: u64 },
+    Mtime { path: PathBuf, expected: FileTime, actual: FileTime },
+    Atime { path: PathBuf, expected: FileTime, actual: FileTime },
+}
+
+impl std::fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing(path) => write!(f, "{path:?}: missing"),
+            Self::Size { path, expected, actual } => write!(f, "{path:?}: size {actual} (expected {expected})"),
+            Self::Mtime { path, expected, actual } => write!(f, "{path:?}: mtime {actual} (expected {expected})"),
+            Self::Atime { path, expected, actual } => write!(f, "{path:?}: atime {actual} (expected {expected})"),
+        }
+    }
+}
+
+/// metadata (size & times) of files & dirs to verify or restore them after clearing
+///
+/// is saved as text: a header line & a line per entry
+/// `<f|d>\t<size>\t<mtime>\t<atime>\t<path>`, times are `<unix secs>.<nanos>`,
+/// in paths `\`, tabs, line breaks, control chars & non UTF-8 bytes are escaped
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Snapshot {
+    pub entries: Vec<SnapshotEntry>,
+}
+
+impl Snapshot {
+    /// metadata of the `paths` (duplicates are skipped)
+    pub fn capture<P: AsRef<Path>>(paths: impl IntoIterator<Item = P>) -> std::io::Result<Self> {
+        let mut seen = std::collections::HashSet::new();
+        let mut entries = vec![];
+        for path in paths {
+            let path = path.as_ref();
+            if !seen.insert(path.to_path_buf()) { continue }
+            entries.push(SnapshotEntry::capture(path)?);
+        }
+        Ok(Self { entries })
+    }
+
+    pub fn write_to(&self, mut w: impl Write) -> std::io::Result<()> {
+        writeln!(w, "{HEADER}")?;
+        for entry in &self.entries {
+            let kind = if entry.is_dir { 'd' } else { 'f' };
+            write!(w, "{kind}\t{}\t{}\t{}\t", entry.size, fmt_time(entry.mtime), fmt_time(entry.atime))?;
+            w.write_all(&escape(&path_to_bytes(&entry.path)))?;
+            writeln!(w)?;
+        }
+        w.flush()
+    }
+
+    pub fn read_from(r: impl BufRead) -> std::io::Result<Self> {
+        let invalid = |line_n: usize, msg: &str| std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("snapshot line {line_n}: {msg}"),
+        );
+
+        let mut entries = vec![];
+        for (i, line) in r.split(b'\n').enumerate() {
+            let line = line?;
+            let line_n = i + 1;
+            if i == 0 {
+                if line != HEADER.as_bytes() { return Err(invalid(line_n, "not a snapshot header")) }
+                continue
+            }
+            if line.is_empty() { continue }
+
+            let mut fields = line.splitn(5, |&b|b == b'\t');
+            let mut field = || fields.next().ok_or_else(||invalid(line_n, "too few fields"));
+            let is_dir = match field()? {
+                b"d" => true,
+                b"f" => false,
+                _ => return Err(invalid(line_n, "unknown entry kind")),
+            };
+            let size = std::str::from_utf8(field()?).ok().and_then(|x|x.parse().ok())
+                .ok_or_else(||invalid(line_n, "bad size"))?;
+            let mtime = parse_time(field()?).ok_or_else(||invalid(line_n, "bad mtime"))?;
+            let atime = parse_time(field()?).ok_or_else(||invalid(line_n, "bad atime"))?;
+            let path = unescape(field()?).ok_or_else(||invalid(line_n, "bad path escape"))?;
+            entries.push(SnapshotEntry { path: path_from_bytes(&path), is_dir, size, mtime, atime });
+        }
+        Ok(Self { entries })
+    }
+
+    /// compares the snapshot with the current state: times (& sizes if `check_size`)
+    pub fn verify(&self, check_size: bool) -> Vec<Mismatch> {
+        let mut mismatches = vec![];
+        for expected in &self.entries {
+            let path = expected.path.clone();
+            let Ok(actual) = SnapshotEntry::capture(&path) else {
+                mismatches.push(Mismatch::Missing(path));
+                continue
+            };
+            if check_size && !expected.is_dir && actual.size != expected.size {
+                mismatches.push(Mismatch::Size { path: path.clone(), expected: expected.size, actual: actual.size });
+            }
+            if actual.mtime != expected.mtime {
+                mismatches.push(Mismatch::Mtime { path: path.clone(), expected: expected.mtime, actual: actual.mtime });
+            }
+            if actual.atime != expected.atime {
+                mismatches.push(Mismatch::Atime { path, expected: expected.atime, actual: actual.atime });
+            }
+        }
+        mismatches
+    }
+
+    /// sets times of existing entries back to the snapshot (missing entries are skipped)
+    ///
+    /// files are restored before dirs
+    ///
+    /// # return
+    /// number of entries whose times were changed
+    pub fn restore_times(&self) -> std::io::Result<usize> {
+        let mut restored = 0;
+        let (dirs, files): (Vec<_>, Vec<_>) = self.entries.iter().partition(|x|x.is_dir);
+        for entry in files.into_iter().chain(dirs) {
+            let Ok(actual) = SnapshotEntry::capture(&entry.path) else { continue };
+            if (actual.mtime, actual.atime) == (entry.mtime, entry.atime) { continue }
+            filetime::set_file_times(&entry.path, entry.atime, entry.mtime)?;
+            restored += 1;
+        }
+        Ok(restored)
+    }
+}
+
+fn fmt_time(time: FileTime) -> String {
+    format!("{}.{:09}", time.unix_seconds(), time.nanoseconds())
+}
+
+fn parse_time(field: &[u8]) -> Option<FileTime> {
+    let (secs, nanos) = std::str::from_utf8(field).ok()?.split_once('.')?;
+    Some(FileTime::from_unix_time(secs.parse().ok()?, nanos.parse().ok()?))
+}
+
+fn escape(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(bytes.len());
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c {
+                '\\' => out.extend_from_slice(b"\\\\"),
+                '\t' => out.extend_from_slice(b"\\t"),
+                '\n' => out.extend_from_slice(b"\\n"),
+                '\r' => out.extend_from_slice(b"\\r"),
+                c if c.is_control() => {
+                    let mut buf = [0; 4];
+                    for &b in c.encode_utf8(&mut buf).as_bytes() {
+                        out.extend_from_slice(format!("\\x{b:02x}").as_bytes());
+                    }
+                }
+                c => out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+            }
+        }
+        for b in chunk.invalid() {
+            out.extend_from_slice(format!("\\x{b:02x}").as_bytes());
+        }
+    }
+    out
+}
+
+fn unescape(bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'\\' {
+            out.push(bytes[i]);
+            i += 1;
+            continue
+        }
+        match bytes.get(i + 1)? {
+            b'\\' => out.push(b'\\'),
+            b't' => out.push(b'\t'),
+            b'n' => out.push(b'\n'),
+            b'r' => out.push(b'\r'),
+            b'x' => {
+                let hex = std::str::from_utf8(bytes.get(i + 2..i + 4)?).ok()?;
+                out.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 2;
+            }
+            _ => return None,
+        }
+        i += 2;
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{escape, unescape};
+
+    #[test]
+    fn test_escape() {
+        let path = b"a\\b\tc\nd\x01\xffe \xc3\xa9";
+        let escaped = escape(path);
+        assert_eq!(escaped, b"a\\\\b\\tc\\nd\\x01\\xffe \xc3\xa9");
+        assert_eq!(unescape(&escaped).as_deref(), Some(&path[..]));
+        assert_eq!(unescape(b"bad\\q"), None);
+    }
+}
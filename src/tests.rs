@@ -44,3 +44,830 @@ fn test_file_clear() -> std::io::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_same_size_fill() -> std::io::Result<()> {
+    let dir = "./tests";
+    let path = format!("{dir}/test_same_size_fill.bin");
+    let path = path.as_str();
+
+    // init file with old mtime:
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(path, "0123456789abcdef0123456789")?;
+    let old_time = filetime::FileTime::from_unix_time(1_000_000_000, 0);
+    filetime::set_file_times(path, old_time, old_time)?;
+
+    crate::clear_act::SameSizeFillF::new_no_filter("xyz").clear_file(path)?;
+
+    let md = std::fs::metadata(path)?;
+    assert_eq!(filetime::FileTime::from_last_modification_time(&md), old_time);
+    assert_eq!(std::fs::read(path)?, b"xyzxyzxyzxyzxyzxyzxyzxyzxy");
+
+    crate::clear_act::SameSizeFillF::new_zero_no_filter().clear_file(path)?;
+    assert_eq!(std::fs::read(path)?, vec![0; 26]);
+
+    Ok(())
+}
+
+#[test]
+fn test_shred_before_clear() -> std::io::Result<()> {
+    use crate::clear_act::ShredPass;
+
+    let dir = "./tests";
+    let path = format!("{dir}/test_shred_before_clear.txt");
+    let path = path.as_str();
+
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(path, "secret customer data")?;
+    let old_time = filetime::FileTime::from_unix_time(1_000_000_000, 0);
+    filetime::set_file_times(path, old_time, old_time)?;
+
+    let mut clearer = crate::clear_act::ConstChangeContF::new_no_filter("stub");
+    clearer.set_shred_passes(vec![ShredPass::Random, ShredPass::Zero]);
+    clearer.clear_file(path)?;
+
+    let md = std::fs::metadata(path)?;
+    assert_eq!(filetime::FileTime::from_last_modification_time(&md), old_time);
+    assert_eq!(std::fs::read_to_string(path)?, "stub");
+
+    Ok(())
+}
+
+#[test]
+fn test_punch_hole() -> std::io::Result<()> {
+    let dir = "./tests";
+    let path = format!("{dir}/test_punch_hole.bin");
+    let path = path.as_str();
+
+    std::fs::create_dir_all(dir)?;
+    {
+        // allocate the storage before punching
+        let mut f = std::fs::File::create(path)?;
+        f.write_all(&vec![0xAB; 256 * 1024])?;
+        f.sync_all()?;
+    }
+    let old_time = filetime::FileTime::from_unix_time(1_000_000_000, 0);
+    filetime::set_file_times(path, old_time, old_time)?;
+
+    let mut clearer = crate::clear_act::PunchHoleF::new_no_filter();
+    clearer.set_fallback(crate::clear_act::PunchFallback::Error);
+    match clearer.clear_file(path) {
+        // the filesystem has no holes
+        Err(err) if err.kind() == std::io::ErrorKind::Unsupported => return Ok(()),
+        result => result?,
+    }
+
+    let md = std::fs::metadata(path)?;
+    assert_eq!(filetime::FileTime::from_last_modification_time(&md), old_time);
+    assert_eq!(md.len(), 256 * 1024);
+    assert_eq!(std::fs::read(path)?, vec![0; 256 * 1024]);
+    assert!(clearer.freed_bytes() > 0);
+    assert!(clearer.freed_bytes() <= 256 * 1024 + 4096);
+
+    Ok(())
+}
+
+#[test]
+fn test_binary_content() -> std::io::Result<()> {
+    use std::borrow::Cow;
+    use crate::ClearDir;
+
+    // empty gzip member
+    const EMPTY_GZIP: &[u8] = &[
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 
+        0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    let dir = "./tests/test_binary_content";
+    let path_f = format!("{dir}/file.gz");
+    let path_d = format!("{dir}/sub/file.gz");
+
+    std::fs::create_dir_all(format!("{dir}/sub"))?;
+    std::fs::write(&path_f, "not a gzip")?;
+    std::fs::write(&path_d, "not a gzip")?;
+
+    crate::ConstChgContF::new_no_filter(EMPTY_GZIP.to_vec()).clear_file(&path_f)?;
+    assert_eq!(std::fs::read(&path_f)?, EMPTY_GZIP);
+
+    let mut clearer = crate::ConstChgContD::new_no_filter(Cow::Borrowed(EMPTY_GZIP));
+    clearer.set_recursive(true);
+    clearer.clear_dir_files(dir)?;
+    assert_eq!(std::fs::read(&path_d)?, EMPTY_GZIP);
+
+    Ok(())
+}
+
+#[test]
+fn test_stub() -> std::io::Result<()> {
+    let dir = "./tests/test_stub";
+    std::fs::create_dir_all(dir)?;
+
+    let files: &[(&str, &[u8], &[u8])] = &[
+        ("list.json", b"[{\"a\": 1}, {\"a\": 2}]", b"[]\n"),
+        ("cfg.toml", b"[a]\nb = 1\n", b""),
+        ("doc.xml", b"<config><x/></config>", b"<config/>\n"),
+        ("sniffed", b"  {\"a\": [1, 2]}", b"{}\n"),
+        ("unknown.txt", b"plain text", b"#fallback"),
+        ("section.ini", b"[section]\nkey = 1\n", b"#fallback"),
+        ("page.html", b"<html><body/></html>", b"#fallback"),
+    ];
+
+    let mut clearer = crate::clear_act::StubF::new_no_filter("#fallback");
+    let old_time = filetime::FileTime::from_unix_time(1_000_000_000, 0);
+    for (name, content, stub) in files {
+        let path = format!("{dir}/{name}");
+        std::fs::write(&path, content)?;
+        filetime::set_file_times(&path, old_time, old_time)?;
+
+        clearer.clear_file(&path)?;
+
+        let md = std::fs::metadata(&path)?;
+        assert_eq!(filetime::FileTime::from_last_modification_time(&md), old_time);
+        assert_eq!(&std::fs::read(&path)?, stub, "{name}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_json_skeleton() -> std::io::Result<()> {
+    let dir = "./tests";
+    let path = format!("{dir}/test_json_skeleton.json");
+    let path = path.as_str();
+
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(path, r#"{"name": "x", "n": 5, "k": 0.5, "ok": true, "none": null,
+        "items": [{"id": 1, "tags": ["a", "b"]}, {"id": 2, "tags": []}], "empty": []}"#)?;
+    let old_time = filetime::FileTime::from_unix_time(1_000_000_000, 0);
+    filetime::set_file_times(path, old_time, old_time)?;
+
+    crate::clear_act::JsonSkeletonF::new_no_filter().clear_file(path)?;
+
+    let md = std::fs::metadata(path)?;
+    assert_eq!(filetime::FileTime::from_last_modification_time(&md), old_time);
+    assert_eq!(
+        std::fs::read_to_string(path)?, 
+        "{\"name\":\"\",\"n\":0,\"k\":0.0,\"ok\":false,\"none\":null,\"items\":[{\"id\":0,\"tags\":[\"\"]}],\"empty\":[]}\n",
+    );
+
+    // invalid JSON stays unchanged
+    std::fs::write(path, "{ not a json")?;
+    assert!(crate::clear_act::JsonSkeletonF::new_no_filter().clear_file(path).is_err());
+    assert_eq!(std::fs::read_to_string(path)?, "{ not a json");
+
+    Ok(())
+}
+
+#[test]
+fn test_json_skeleton_dir() -> std::io::Result<()> {
+    use crate::ClearDir;
+
+    let dir = "./tests/test_json_skeleton_dir";
+    let _ = std::fs::remove_dir_all(dir);
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(format!("{dir}/a.json"), "{ not a json")?;
+    std::fs::write(format!("{dir}/b.json"), r#"{"n": 5}"#)?;
+    std::fs::write(format!("{dir}/empty.json"), "")?;
+    std::fs::write(format!("{dir}/truncated.json"), r#"{"n": [1, 2"#)?;
+
+    // a non JSON file doesn't stop the traversal
+    let mut clearer = crate::WrapD::wrap_no_filter(crate::clear_act::JsonSkeletonF::new_no_filter());
+    let mut report = clearer.clear_dir_files_report(dir)?;
+    report.invalid.sort();
+
+    let p = |path: &str| std::path::Path::new(dir).join(path);
+    assert_eq!(report.invalid, vec![p("a.json"), p("empty.json"), p("truncated.json")]);
+    assert_eq!(report.cleared, vec![std::path::Path::new(dir).join("b.json")]);
+    assert_eq!(std::fs::read_to_string(format!("{dir}/a.json"))?, "{ not a json");
+    assert_eq!(std::fs::read_to_string(format!("{dir}/b.json"))?, "{\"n\":0}\n");
+    assert_eq!(std::fs::read_to_string(format!("{dir}/empty.json"))?, "");
+    assert_eq!(std::fs::read_to_string(format!("{dir}/truncated.json"))?, r#"{"n": [1, 2"#);
+
+    Ok(())
+}
+
+#[test]
+fn test_dummy_deterministic() -> std::io::Result<()> {
+    use crate::clear_act::{DummyF, DummyKind};
+
+    let dir = "./tests/test_dummy_deterministic";
+    std::fs::create_dir_all(dir)?;
+    let path_a = format!("{dir}/a.txt");
+    let path_b = format!("{dir}/b.txt");
+    let init = "x".repeat(70_000);
+    let old_time = filetime::FileTime::from_unix_time(1_000_000_000, 0);
+
+    let mut results = vec![];
+    for kind in [DummyKind::Lorem, DummyKind::Lorem, DummyKind::Random] {
+        let mut clearer = DummyF::new_no_filter(kind, 42);
+        for path in [&path_a, &path_b] {
+            std::fs::write(path, &init)?;
+            filetime::set_file_times(path, old_time, old_time)?;
+            clearer.clear_file(path)?;
+
+            let md = std::fs::metadata(path)?;
+            assert_eq!(filetime::FileTime::from_last_modification_time(&md), old_time);
+            assert_eq!(md.len(), init.len() as u64);
+            results.push(std::fs::read(path)?);
+        }
+    }
+
+    // same seed & path => same content
+    assert_eq!(results[0], results[2]);
+    assert_eq!(results[1], results[3]);
+    // other path or kind => other content
+    assert_ne!(results[0], results[1]);
+    assert_ne!(results[0], results[4]);
+    assert!(results[0].iter().all(|b|b.is_ascii_lowercase() || *b == b' ' || *b == b'\n'));
+
+    Ok(())
+}
+
+#[test]
+fn test_dummy_path_spelling() -> std::io::Result<()> {
+    use crate::clear_act::{DummyF, DummyKind};
+    use crate::ClearDir;
+
+    let dir = "tests/test_dummy_path_spelling/d";
+    std::fs::create_dir_all(dir)?;
+    let abs_dir = std::fs::canonicalize(dir)?;
+    let spellings = [dir.to_string(), format!("./{dir}"), abs_dir.to_string_lossy().into_owned()];
+
+    let mut results = vec![];
+    let mut clear = |clear: &mut dyn FnMut(&str) -> std::io::Result<()>| -> std::io::Result<()> {
+        for spelling in &spellings {
+            std::fs::write(format!("{dir}/a.txt"), "x".repeat(100))?;
+            clear(spelling)?;
+            results.push(std::fs::read(format!("{dir}/a.txt"))?);
+        }
+        Ok(())
+    };
+    // the file, the dir & the file with the dir as the root
+    clear(&mut |d|DummyF::new_no_filter(DummyKind::Lorem, 7).clear_file(format!("{d}/a.txt")))?;
+    clear(&mut |d|crate::WrapD::wrap_no_filter(DummyF::new_no_filter(DummyKind::Lorem, 7)).clear_dir_files(d))?;
+    clear(&mut |d| {
+        let mut clearer = DummyF::new_no_filter(DummyKind::Lorem, 7);
+        clearer.set_root(Some(d.into()))?;
+        clearer.clear_file(format!("{dir}/a.txt"))
+    })?;
+
+    assert_eq!(results.len(), 9);
+    assert!(results.iter().all(|x|*x == results[0]));
+
+    Ok(())
+}
+
+#[test]
+fn test_keep_lines() -> std::io::Result<()> {
+    use crate::clear_act::{KeepLinesF, LineMask};
+
+    let dir = "./tests";
+    let path = format!("{dir}/test_keep_lines.txt");
+    let path = path.as_str();
+    let init = "fn main() {\r\n    println!(\"ß\");\n}\r\n\nlast";
+
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(path, init)?;
+    let old_time = filetime::FileTime::from_unix_time(1_000_000_000, 0);
+    filetime::set_file_times(path, old_time, old_time)?;
+
+    KeepLinesF::new_no_filter().clear_file(path)?;
+    let md = std::fs::metadata(path)?;
+    assert_eq!(filetime::FileTime::from_last_modification_time(&md), old_time);
+    assert_eq!(std::fs::read_to_string(path)?, "\r\n\n\r\n\n");
+
+    std::fs::write(path, init)?;
+    let mut clearer = KeepLinesF::new_no_filter();
+    clearer.set_mask(LineMask::Mask(b'x'));
+    clearer.set_keep_endings(false);
+    clearer.clear_file(path)?;
+    assert_eq!(std::fs::read_to_string(path)?, "xx xxxxxx x\n    xxxxxxxxxxxxxx\nx\n\nxxxx");
+
+    Ok(())
+}
+
+#[test]
+fn test_atomic_clear() -> std::io::Result<()> {
+    use crate::clear_act::{ClearMode, KeepLinesF};
+
+    let dir = "./tests/test_atomic_clear";
+    let path = format!("{dir}/file.txt");
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(&path, "a\nb\nc\n")?;
+
+    #[cfg(unix)]
+    std::fs::set_permissions(&path, std::os::unix::fs::PermissionsExt::from_mode(0o640))?;
+    let old_time = filetime::FileTime::from_unix_time(1_000_000_000, 0);
+    filetime::set_file_times(&path, old_time, old_time)?;
+    filetime::set_file_times(dir, old_time, old_time)?;
+
+    let mut clearer = crate::WrapD::wrap_no_filter(KeepLinesF::new_no_filter());
+    clearer.set_clear_mode(ClearMode::Atomic);
+    clearer.clear_file(&path)?;
+
+    let md = std::fs::metadata(&path)?;
+    assert_eq!(filetime::FileTime::from_last_modification_time(&md), old_time);
+    assert_eq!(std::fs::read_to_string(&path)?, "\n\n\n");
+    #[cfg(unix)]
+    assert_eq!(std::os::unix::fs::PermissionsExt::mode(&md.permissions()) & 0o777, 0o640);
+
+    // parent dir times are restored & there is no temp file left
+    let dir_md = std::fs::metadata(dir)?;
+    assert_eq!(filetime::FileTime::from_last_modification_time(&dir_md), old_time);
+    assert_eq!(std::fs::read_dir(dir)?.count(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_atomic_restrictions() -> std::io::Result<()> {
+    use crate::clear_act::{ClearMode, ShredPass};
+
+    let dir = "./tests/test_atomic_restrictions";
+    let path = format!("{dir}/secret.txt");
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(&path, "secret")?;
+
+    // shred passes would overwrite the temp file instead of the original data
+    let mut clearer = crate::clear_act::ConstChangeContF::new_no_filter("");
+    clearer.set_shred_passes(vec![ShredPass::Zero]);
+    let err = clearer.try_clear_file_mode(&path, ClearMode::Atomic).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(std::fs::read_to_string(&path)?, "secret");
+
+    // the temp file with the original content is private until the permissions are copied
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        struct TempMode(u32);
+        impl ClearFile for TempMode {
+            fn is_file_allow(&mut self, _: impl AsRef<std::path::Path>) -> bool { true }
+            fn clear_action(&mut self, f: &mut std::fs::File, _: &std::fs::Metadata) -> std::io::Result<()> {
+                self.0 = f.metadata()?.permissions().mode() & 0o777;
+                Ok(())
+            }
+            fn is_truncate(&mut self) -> bool { false }
+        }
+
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+        let mut clearer = TempMode(0);
+        clearer.try_clear_file_mode(&path, ClearMode::Atomic)?;
+        assert_eq!(clearer.0, 0o600);
+        assert_eq!(std::fs::metadata(&path)?.permissions().mode() & 0o777, 0o600);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_dir_times_preserved() -> std::io::Result<()> {
+    use crate::clear_act::{ClearMode, ConstChangeContD};
+    use crate::ClearDir;
+
+    // not in `./tests`: watchers of the project dir (IDE, indexers) can read new dirs & bump their atime
+    let dir = std::env::temp_dir().join("cfpm_test_dir_times_preserved");
+    let dir = dir.to_str().unwrap();
+    let sub = format!("{dir}/sub");
+    std::fs::create_dir_all(&sub)?;
+    std::fs::write(format!("{dir}/a.txt"), "a")?;
+    std::fs::write(format!("{sub}/b.txt"), "b")?;
+
+    let old_atime = filetime::FileTime::from_unix_time(1_000_000_000, 0);
+    let old_mtime = filetime::FileTime::from_unix_time(1_000_000_100, 0);
+    for dir in [dir, sub.as_str()] {
+        filetime::set_file_times(dir, old_atime, old_mtime)?;
+    }
+
+    // atomic mode renames inside of the dirs
+    let mut clearer = ConstChangeContD::new_no_filter("");
+    clearer.set_recursive(true);
+    clearer.set_clear_mode(ClearMode::Atomic);
+    clearer.clear_dir_files(dir)?;
+
+    for dir in [dir, sub.as_str()] {
+        let md = std::fs::metadata(dir)?;
+        assert_eq!(filetime::FileTime::from_last_access_time(&md), old_atime);
+        assert_eq!(filetime::FileTime::from_last_modification_time(&md), old_mtime);
+    }
+    assert_eq!(std::fs::read(format!("{sub}/b.txt"))?, b"");
+
+    Ok(())
+}
+
+#[test]
+fn test_atime_preserved_on_error() -> std::io::Result<()> {
+    let dir = "./tests";
+    let path = format!("{dir}/test_atime_preserved_on_error.json");
+    let path = path.as_str();
+
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(path, "{ not a json")?;
+    // atime older than mtime: `relatime` mounts would update it on read
+    let old_atime = filetime::FileTime::from_unix_time(1_000_000_000, 0);
+    let old_mtime = filetime::FileTime::from_unix_time(1_000_000_100, 0);
+    filetime::set_file_times(path, old_atime, old_mtime)?;
+
+    // the file is read but the action fails
+    assert!(crate::clear_act::JsonSkeletonF::new_no_filter().clear_file(path).is_err());
+
+    let md = std::fs::metadata(path)?;
+    assert_eq!(filetime::FileTime::from_last_access_time(&md), old_atime);
+    assert_eq!(filetime::FileTime::from_last_modification_time(&md), old_mtime);
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_hard_links() -> std::io::Result<()> {
+    use crate::clear_act::{ClearMode, ConstChangeContD, HardLinkPolicy};
+    use crate::ClearDir;
+
+    let dir = "./tests/test_hard_links";
+    let _ = std::fs::remove_dir_all(dir);
+    std::fs::create_dir_all(format!("{dir}/in"))?;
+    let outside = format!("{dir}/outside.txt");
+    let path_a = format!("{dir}/in/a.txt");
+    let path_b = format!("{dir}/in/b.txt");
+
+    // `ClearOnce` clears in place even in the atomic mode
+    let expected = [
+        (HardLinkPolicy::ClearOnce, ClearMode::InPlace, 1, ["", "", ""]),
+        (HardLinkPolicy::ClearOnce, ClearMode::Atomic, 1, ["", "", ""]),
+        (HardLinkPolicy::Skip, ClearMode::InPlace, 0, ["data", "data", "data"]),
+        (HardLinkPolicy::BreakLink, ClearMode::InPlace, 2, ["data", "", ""]),
+    ];
+    for (policy, mode, cleared, contents) in expected {
+        for path in [&outside, &path_a, &path_b] { let _ = std::fs::remove_file(path); }
+        std::fs::write(&outside, "data")?;
+        std::fs::hard_link(&outside, &path_a)?;
+        std::fs::hard_link(&outside, &path_b)?;
+
+        let mut clearer = ConstChangeContD::new_no_filter("");
+        clearer.set_hard_link_policy(policy);
+        clearer.set_clear_mode(mode);
+        let report = clearer.clear_dir_files_report(format!("{dir}/in"))?;
+
+        assert_eq!(report.cleared.len(), cleared, "{policy:?}");
+        assert_eq!(report.hard_links.len(), 1, "{policy:?}");
+        assert_eq!(report.hard_links[0].paths.len(), 2, "{policy:?}");
+        assert_eq!(report.hard_links[0].nlink, 3, "{policy:?}");
+        for (path, content) in [&outside, &path_a, &path_b].into_iter().zip(contents) {
+            assert_eq!(std::fs::read_to_string(path)?, content, "{policy:?}: {path}");
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_ignore_filter() -> std::io::Result<()> {
+    use std::collections::BTreeSet;
+    use crate::clear_act::ConstChangeContD;
+    use crate::filter::{IgnoreFilter, IgnoreMode};
+    use crate::ClearDir;
+
+    let dir = "./tests/test_ignore_filter";
+    let files = [
+        // `.git` marks the repo root
+        (".git/info/exclude", "*.excl\n"),
+        (".gitignore", "*.log\n/build/\n!keep.log\n"),
+        ("a.txt", "x"), ("a.log", "x"), ("keep.log", "x"), ("x.excl", "x"),
+        ("build/out.txt", "x"),
+        ("sub/.gitignore", "!b.log\n"),
+        ("sub/.ignore", "a.txt\n"),
+        ("sub/a.txt", "x"), ("sub/b.log", "x"),
+        ("sub/build/out.txt", "x"),
+    ];
+    let expected = [
+        (IgnoreMode::Unignored, vec![
+            ".gitignore", "a.txt", "keep.log", "sub/.gitignore", "sub/.ignore", "sub/b.log", "sub/build/out.txt",
+        ]),
+        (IgnoreMode::Ignored, vec!["a.log", "x.excl", "build/out.txt", "sub/a.txt"]),
+    ];
+
+    for (mode, cleared) in expected {
+        let _ = std::fs::remove_dir_all(dir);
+        for (path, content) in files {
+            let path = std::path::Path::new(dir).join(path);
+            std::fs::create_dir_all(path.parent().unwrap())?;
+            std::fs::write(path, content)?;
+        }
+
+        let filter = IgnoreFilter::new(mode);
+        let mut clearer = ConstChangeContD::new_no_filter("");
+        clearer.set_recursive(true);
+        clearer.set_ignore_filter(Some(&filter));
+        let report = clearer.clear_dir_files_report(dir)?;
+
+        let report: BTreeSet<_> = report.cleared.iter()
+            .map(|path|path.strip_prefix(dir).unwrap().to_path_buf())
+            .collect();
+        let cleared: BTreeSet<_> = cleared.into_iter().map(std::path::PathBuf::from).collect();
+        assert_eq!(report, cleared, "{mode:?}");
+        assert_eq!(std::fs::read_to_string(format!("{dir}/.git/info/exclude"))?, "*.excl\n");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_git_paths() -> std::io::Result<()> {
+    use std::process::Command;
+    use crate::git::{git_paths, GitSelect};
+
+    let dir = "./tests/test_git_paths";
+    let _ = std::fs::remove_dir_all(dir);
+    std::fs::create_dir_all(format!("{dir}/sub"))?;
+    std::fs::write(format!("{dir}/a.txt"), "a")?;
+    std::fs::write(format!("{dir}/sub/b.txt"), "b")?;
+
+    let git = |args: &[&str]| Command::new("git").arg("-C").arg(dir).args(args).output();
+    // no git => nothing to test
+    if git(&["init", "-q"]).is_err() { return Ok(()) }
+    git(&["add", "."])?;
+    git(&["-c", "user.name=test", "-c", "user.email=test@test", "commit", "-q", "-m", "init"])?;
+    std::fs::write(format!("{dir}/a.txt"), "changed")?;
+    std::fs::write(format!("{dir}/new.txt"), "new")?;
+
+    let sorted = |mut paths: Vec<std::path::PathBuf>| { paths.sort(); paths };
+    let p = |path: &str| std::path::Path::new(dir).join(path);
+    assert_eq!(sorted(git_paths(dir, &GitSelect::Tracked)?), vec![p("a.txt"), p("sub/b.txt")]);
+    assert_eq!(git_paths(dir, &GitSelect::Untracked)?, vec![p("new.txt")]);
+    assert_eq!(git_paths(dir, &GitSelect::Modified)?, vec![p("a.txt")]);
+    assert!(git_paths(format!("{dir}/sub"), &GitSelect::ChangedSince("HEAD".into()))?.is_empty());
+
+    // a commit that looks like an option is not passed to git as an option
+    let out = format!("{dir}/out.txt");
+    let err = git_paths(dir, &GitSelect::ChangedSince(format!("--output={out}"))).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(!std::path::Path::new(&out).exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_clear_paths_dir_filter() -> std::io::Result<()> {
+    use crate::ClearDir;
+
+    let dir = "./tests/test_clear_paths_dir_filter";
+    let _ = std::fs::remove_dir_all(dir);
+    std::fs::create_dir_all(format!("{dir}/target/sub"))?;
+    std::fs::write(format!("{dir}/a.txt"), "a")?;
+    std::fs::write(format!("{dir}/target/sub/x.txt"), "x")?;
+
+    // the nested dir is allowed itself but it is inside of the denied one
+    let dir_filter = crate::filter::DirFilter::new(None, Some("/target$")).unwrap();
+    let mut clearer = crate::clear_act::ConstChangeContD::new_no_file_filter("", &dir_filter);
+    let p = |path: &str| std::path::Path::new(dir).join(path);
+    let report = clearer.clear_paths_report(dir, [p("a.txt"), p("target/sub/x.txt")])?;
+
+    assert_eq!(report.cleared, vec![p("a.txt")]);
+    assert_eq!(std::fs::read_to_string(p("target/sub/x.txt"))?, "x");
+
+    Ok(())
+}
+
+#[test]
+fn test_git_mark_index() -> std::io::Result<()> {
+    use std::process::Command;
+    use crate::clear_act::ConstChangeContD;
+    use crate::git::{mark_index, IndexMark};
+    use crate::ClearDir;
+
+    let dir = "./tests/test_git_mark_index";
+    let _ = std::fs::remove_dir_all(dir);
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(format!("{dir}/a.txt"), "some content")?;
+    std::fs::write(format!("{dir}/b.txt"), "b")?;
+
+    let git = |args: &[&str]| Command::new("git").arg("-C").arg(dir).args(args).output();
+    // no git => nothing to test
+    if git(&["init", "-q"]).is_err() { return Ok(()) }
+    git(&["add", "a.txt"])?;
+    git(&["-c", "user.name=test", "-c", "user.email=test@test", "commit", "-q", "-m", "init"])?;
+
+    let mut fd_changer = ConstChangeContD::new_no_filter("");
+    let report = fd_changer.clear_dir_files_report(dir)?;
+    assert_eq!(report.cleared.len(), 2);
+
+    assert_eq!(crate::git::work_tree_root(format!("{dir}/a.txt")), Some(std::fs::canonicalize(dir)?));
+
+    // untracked `b.txt` is skipped
+    let marked = mark_index(dir, &report.cleared, IndexMark::AssumeUnchanged)?;
+    assert_eq!(marked, vec![std::path::PathBuf::from("a.txt")]);
+    assert!(git(&["status", "--porcelain", "--untracked-files=no"])?.stdout.is_empty());
+    // `h` tag is for assume-unchanged entries
+    assert_eq!(git(&["ls-files", "-v"])?.stdout, b"h a.txt\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_path_guard() -> std::io::Result<()> {
+    use crate::clear_act::ConstChangeContD;
+    use crate::guard::{PathGuard, Protection};
+    use crate::ClearDir;
+
+    let dir = "./tests/test_path_guard";
+    let _ = std::fs::remove_dir_all(dir);
+    std::fs::create_dir_all(format!("{dir}/root/.git/objects"))?;
+    std::fs::create_dir_all(format!("{dir}/root/keep"))?;
+    for path in ["outside.txt", "root/a.txt", "root/.git/objects/obj", "root/keep/k.txt"] {
+        std::fs::write(format!("{dir}/{path}"), "content")?;
+    }
+
+    let mut guard = PathGuard::new();
+    guard.set_root(format!("{dir}/root"))?;
+    guard.add_protected(format!("{dir}/root/keep"))?;
+
+    assert_eq!(guard.protection("/"), Some(Protection::FsRoot));
+    assert_eq!(guard.protection(format!("{dir}/root/.git/objects")), Some(Protection::GitInternals));
+    assert!(matches!(guard.protection(format!("{dir}/outside.txt")), Some(Protection::OutsideRoot(_))));
+    assert!(matches!(guard.protection(format!("{dir}/root/keep/k.txt")), Some(Protection::Listed(_))));
+    assert_eq!(guard.check(format!("{dir}/root/keep")).unwrap_err().kind(), std::io::ErrorKind::PermissionDenied);
+    assert!(guard.check(format!("{dir}/root")).is_ok());
+
+    // the home dir & dirs that contain it, but not its subdirs
+    std::fs::create_dir_all(format!("{dir}/users/me/docs"))?;
+    let mut home_guard = PathGuard::new();
+    home_guard.set_home(format!("{dir}/users/me"))?;
+    assert_eq!(home_guard.protection(format!("{dir}/users/me")), Some(Protection::Home));
+    assert_eq!(home_guard.protection(format!("{dir}/users")), Some(Protection::Home));
+    assert_eq!(home_guard.protection(dir), Some(Protection::Home));
+    assert_eq!(home_guard.protection(format!("{dir}/users/me/docs")), None);
+    assert_eq!(home_guard.protection(format!("{dir}/root")), None);
+
+    let mut clearer = ConstChangeContD::new_no_filter("");
+    clearer.set_recursive(true);
+    clearer.set_path_guard(Some(&guard));
+    let report = clearer.clear_dir_files_report(dir)?;
+    assert!(report.cleared.is_empty());
+    let report = clearer.clear_dir_files_report(format!("{dir}/root"))?;
+    assert_eq!(report.cleared, vec![std::path::PathBuf::from(format!("{dir}/root/a.txt"))]);
+    for path in ["outside.txt", "root/.git/objects/obj", "root/keep/k.txt"] {
+        assert_eq!(std::fs::read_to_string(format!("{dir}/{path}"))?, "content");
+    }
+
+    // only the built-in protections are disabled
+    let mut guard = guard.clone();
+    guard.set_allow_dangerous(true);
+    assert_eq!(guard.protection("/"), None);
+    assert_eq!(guard.protection(format!("{dir}/root/.git/objects")), None);
+    assert_eq!(guard.protection(format!("{dir}/outside.txt")), None);
+    assert!(matches!(guard.protection(format!("{dir}/root/keep/k.txt")), Some(Protection::Listed(_))));
+
+    Ok(())
+}
+
+#[test]
+fn test_dry_run_snapshot() -> std::io::Result<()> {
+    use crate::clear_act::ConstChangeContD;
+    use crate::snapshot::{Mismatch, Snapshot};
+    use crate::ClearDir;
+
+    // not in `./tests`: see `test_dir_times_preserved`
+    let dir = std::env::temp_dir().join("cfpm_test_dry_run_snapshot");
+    let dir = dir.to_str().unwrap();
+    let _ = std::fs::remove_dir_all(dir);
+    std::fs::create_dir_all(dir)?;
+    let path = format!("{dir}/a\tb.txt");
+    std::fs::write(&path, "content")?;
+    let old_time = filetime::FileTime::from_unix_time(1_000_000_000, 0);
+    filetime::set_file_times(&path, old_time, old_time)?;
+
+    let mut clearer = ConstChangeContD::new_no_filter("");
+    clearer.set_dry_run(true);
+    let report = clearer.clear_dir_files_report(dir)?;
+    assert_eq!(report.cleared, vec![std::path::PathBuf::from(&path)]);
+    assert_eq!(std::fs::read_to_string(&path)?, "content");
+
+    let mut saved = vec![];
+    Snapshot::capture([&path, dir])?.write_to(&mut saved)?;
+    let snapshot = Snapshot::read_from(&saved[..])?;
+    assert_eq!(snapshot.entries.len(), 2);
+    assert_eq!(snapshot.entries[0].mtime, old_time);
+
+    clearer.set_dry_run(false);
+    clearer.clear_dir_files(dir)?;
+    assert!(snapshot.verify(false).is_empty());
+    assert!(matches!(snapshot.verify(true)[..], [Mismatch::Size { expected: 7, actual: 0, .. }]));
+
+    filetime::set_file_mtime(&path, filetime::FileTime::from_unix_time(1_500_000_000, 0))?;
+    assert_eq!(snapshot.restore_times()?, 1);
+    assert!(snapshot.verify(false).is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_max_depth() -> std::io::Result<()> {
+    use crate::clear_act::ConstChangeContD;
+    use crate::ClearDir;
+
+    let dir = "./tests/test_max_depth";
+    let _ = std::fs::remove_dir_all(dir);
+    std::fs::create_dir_all(format!("{dir}/a/b"))?;
+    for path in ["0.txt", "a/1.txt", "a/b/2.txt"] {
+        std::fs::write(format!("{dir}/{path}"), "content")?;
+    }
+
+    let mut clearer = ConstChangeContD::new_no_filter("");
+    clearer.set_recursive(true);
+    clearer.set_max_depth(Some(1));
+    let mut cleared = clearer.clear_dir_files_report(dir)?.cleared;
+    cleared.sort();
+    let p = |path: &str| std::path::Path::new(dir).join(path);
+    assert_eq!(cleared, vec![p("0.txt"), p("a/1.txt")]);
+    assert_eq!(std::fs::read_to_string(format!("{dir}/a/b/2.txt"))?, "content");
+
+    Ok(())
+}
+
+#[test]
+fn test_config_profiles() -> std::io::Result<()> {
+    use crate::config::{find_profile, Config};
+
+    let dir = "./tests/test_config_profiles";
+    std::fs::create_dir_all(dir)?;
+    let project = format!("{dir}/{}", Config::PROJECT_FILE);
+    let user = format!("{dir}/user.toml");
+    std::fs::write(&project, "[profile.fixtures]\ndirs = [\"fx\"]\nmax-depth = 1\nwhite-list = '\\.json$'\njson-skeleton = true\n")?;
+    std::fs::write(&user, "[profile.fixtures]\nstub = true\n\n[profile.user]\nrecursive = true\nprotect = [\"/data\"]\n")?;
+
+    let fixtures = find_profile([&project, &user], "fixtures")?.unwrap();
+    assert_eq!(fixtures.dirs, vec![std::path::Path::new(dir).join("fx")]);
+    assert_eq!(fixtures.dir_depth(), Some(1));
+    assert_eq!(fixtures.white_list.as_deref(), Some("\\.json$"));
+    assert!(fixtures.json_skeleton && !fixtures.stub);
+
+    let user = find_profile([format!("{dir}/missing.toml"), project, user], "user")?.unwrap();
+    assert_eq!(user.dir_depth(), None);
+    assert_eq!(user.protect, vec![std::path::PathBuf::from("/data")]);
+
+    assert!(Config::parse("[profile.x]\nunknown = 1\n").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_file_confirm() -> std::io::Result<()> {
+    use crate::clear_act::ConstChangeContD;
+    use crate::ClearDir;
+
+    let dir = "./tests/test_file_confirm";
+    let _ = std::fs::remove_dir_all(dir);
+    std::fs::create_dir_all(dir)?;
+    for name in ["a.txt", "b.txt", "c.log"] {
+        std::fs::write(format!("{dir}/{name}"), "content")?;
+    }
+
+    let file_filter = crate::filter::FileFilter::new(Some("\\.txt$"), None).unwrap();
+    let mut asked = vec![];
+    let mut clearer = ConstChangeContD::new_no_dir_filter("", &file_filter);
+    clearer.set_file_confirm(Some(Box::new(|path: &std::path::Path| {
+        asked.push(path.to_path_buf());
+        path.ends_with("a.txt")
+    })));
+    let report = clearer.clear_dir_files_report(dir)?;
+    drop(clearer);
+
+    // only files allowed by filters are asked
+    asked.sort();
+    assert_eq!(asked, vec![std::path::Path::new(dir).join("a.txt"), std::path::Path::new(dir).join("b.txt")]);
+    assert_eq!(report.cleared, vec![std::path::Path::new(dir).join("a.txt")]);
+    assert_eq!(std::fs::read_to_string(format!("{dir}/b.txt"))?, "content");
+
+    Ok(())
+}
+
+#[test]
+fn test_progress() -> std::io::Result<()> {
+    use crate::clear_act::{ConstChangeContD, ClearProgress};
+    use crate::ClearDir;
+
+    let dir = "./tests/test_progress";
+    let _ = std::fs::remove_dir_all(dir);
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(format!("{dir}/a.txt"), vec![b'a'; 64 * 1024])?;
+    std::fs::write(format!("{dir}/b.log"), "content")?;
+
+    let file_filter = crate::filter::FileFilter::new(Some("\\.txt$"), None).unwrap();
+    let mut calls = vec![];
+    let mut clearer = ConstChangeContD::new_no_dir_filter("", &file_filter);
+    clearer.set_progress(Some(Box::new(|progress: &ClearProgress, path: &std::path::Path| {
+        calls.push((*progress, path.to_path_buf()));
+    })));
+    clearer.clear_dir_files(dir)?;
+    drop(clearer);
+
+    // a call per visited file (not only cleared ones)
+    assert_eq!(calls.len(), 2);
+    let last = calls.last().unwrap().0;
+    assert_eq!((last.visited, last.cleared), (2, 1));
+    assert!(last.freed_bytes > 0);
+    assert_eq!(std::fs::read_to_string(format!("{dir}/b.log"))?, "content");
+
+    Ok(())
+}